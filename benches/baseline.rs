@@ -1,7 +1,6 @@
 // testing rust allocator vs onsen without doing any work on the data
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 
-use onsen;
 use onsen::PoolApi;
 
 #[cfg(feature = "tbox")]
@@ -31,7 +30,7 @@ fn rust_box_many(howmany: usize) {
     }
 }
 
-fn onsen_box_many<'a>(howmany: usize, pool: &'a onsen::RcPool<u64>) {
+fn onsen_box_many(howmany: usize, pool: &onsen::RcPool<u64>) {
     let mut keep = Vec::with_capacity(howmany);
     for _ in 0..howmany {
         keep.push(onsen::Box::new(0u64, pool));
@@ -46,13 +45,28 @@ fn onsen_tbox_many(howmany: usize) {
     }
 }
 
+// same as above for small types, where the size of the pool entries matters
+fn rust_box_many_small<V: Default>(howmany: usize) {
+    let mut keep = Vec::with_capacity(howmany);
+    for _ in 0..howmany {
+        keep.push(Box::new(V::default()));
+    }
+}
+
+fn onsen_box_many_small<V: Default>(howmany: usize, pool: &onsen::RcPool<V>) {
+    let mut keep = Vec::with_capacity(howmany);
+    for _ in 0..howmany {
+        keep.push(onsen::Box::new(V::default(), pool));
+    }
+}
+
 // allocate many elements into a preallocated Vec and drop random elements on the way
 
 #[inline(always)]
 fn fast_prng(state: &mut u32) -> u32 {
     let rand = *state;
     *state = rand << 1 ^ ((rand >> 30) & 1) ^ ((rand >> 2) & 1);
-    return rand;
+    rand
 }
 
 fn rust_box_many_with_drop(howmany: usize, drop_percent: u32) {
@@ -67,7 +81,7 @@ fn rust_box_many_with_drop(howmany: usize, drop_percent: u32) {
     }
 }
 
-fn onsen_box_many_with_drop<'a>(howmany: usize, drop_percent: u32, pool: &'a onsen::RcPool<u64>) {
+fn onsen_box_many_with_drop(howmany: usize, drop_percent: u32, pool: &onsen::RcPool<u64>) {
     let mut state = 0xbabeface_u32;
     let mut keep = Vec::with_capacity(howmany);
     for _ in 0..howmany {
@@ -92,10 +106,29 @@ fn onsen_tbox_many_with_drop(howmany: usize, drop_percent: u32) {
     }
 }
 
+// free and reallocate random elements of a pool made of many small blocks
+fn rust_box_churn(keep: &mut [Option<Box<u64>>], cycles: usize) {
+    let mut state = 0xbabeface_u32;
+    for _ in 0..cycles {
+        let pos = fast_prng(&mut state) as usize % keep.len();
+        keep[pos] = None;
+        keep[pos] = Some(Box::new(0u64));
+    }
+}
+
+fn onsen_box_churn(keep: &mut [Option<onsen::Box<u64>>], cycles: usize, pool: &onsen::RcPool<u64>) {
+    let mut state = 0xbabeface_u32;
+    for _ in 0..cycles {
+        let pos = fast_prng(&mut state) as usize % keep.len();
+        keep[pos] = None;
+        keep[pos] = Some(onsen::Box::new(0u64, pool));
+    }
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let mut baseline = c.benchmark_group("baseline drop");
 
-    baseline.bench_function("rust box drop", |b| b.iter(|| rust_box_drop()));
+    baseline.bench_function("rust box drop", |b| b.iter(rust_box_drop));
 
     baseline.bench_function("onsen box drop", {
         |b| {
@@ -109,7 +142,7 @@ fn criterion_benchmark(c: &mut Criterion) {
     #[cfg(feature = "tbox")]
     baseline.bench_function("onsen tbox drop", move |b| {
         onsen::TBox::<u64, Bench>::pool().acquire().unwrap();
-        b.iter(|| onsen_tbox_drop());
+        b.iter(onsen_tbox_drop);
         onsen::TBox::<u64, Bench>::pool().release().unwrap();
     });
 
@@ -145,6 +178,45 @@ fn criterion_benchmark(c: &mut Criterion) {
         });
     }
 
+    drop(baseline);
+    let mut baseline = c.benchmark_group("baseline keep small types");
+
+    for size in [1000, 10000, 100000].iter() {
+        baseline.throughput(Throughput::Elements(*size as u64));
+
+        baseline.bench_with_input(BenchmarkId::new("rust box u8", size), &size, {
+            |b, &s| {
+                b.iter(|| {
+                    rust_box_many_small::<u8>(*s);
+                })
+            }
+        });
+
+        baseline.bench_with_input(BenchmarkId::new("onsen box u8", size), &size, {
+            move |b, &s| {
+                let pool: onsen::RcPool<u8> = onsen::RcPool::new();
+                pool.with_min_entries(1000);
+                b.iter(|| onsen_box_many_small(*s, &pool));
+            }
+        });
+
+        baseline.bench_with_input(BenchmarkId::new("rust box u32", size), &size, {
+            |b, &s| {
+                b.iter(|| {
+                    rust_box_many_small::<u32>(*s);
+                })
+            }
+        });
+
+        baseline.bench_with_input(BenchmarkId::new("onsen box u32", size), &size, {
+            move |b, &s| {
+                let pool: onsen::RcPool<u32> = onsen::RcPool::new();
+                pool.with_min_entries(1000);
+                b.iter(|| onsen_box_many_small(*s, &pool));
+            }
+        });
+    }
+
     drop(baseline);
     let mut baseline = c.benchmark_group("baseline with 50 percent drop");
 
@@ -177,6 +249,58 @@ fn criterion_benchmark(c: &mut Criterion) {
         });
     }
 
+    drop(baseline);
+    let mut baseline = c.benchmark_group("baseline many blocks with 50 percent drop");
+
+    // Fixed size blocks of 1000 entries, with 50 percent drops about two thirds of the
+    // elements stay alive, thus the pool grows to 34, 51 and 67 blocks
+    for size in [50000, 75000, 100000].iter() {
+        baseline.throughput(Throughput::Elements(*size as u64));
+
+        baseline.bench_with_input(BenchmarkId::new("rust box", size), &size, {
+            |b, &s| {
+                b.iter(|| {
+                    rust_box_many_with_drop(*s, 50);
+                })
+            }
+        });
+
+        baseline.bench_with_input(BenchmarkId::new("onsen box", size), &size, {
+            move |b, &s| {
+                let pool: onsen::RcPool<u64> = onsen::RcPool::new();
+                pool.with_growth_policy(onsen::GrowthPolicy::Fixed { entries: 1000 });
+                b.iter(|| onsen_box_many_with_drop(*s, 50, &pool));
+            }
+        });
+    }
+
+    drop(baseline);
+    let mut baseline = c.benchmark_group("baseline churn in many small blocks");
+
+    // Fixed size blocks of 64 entries, all of them in use, each cycle frees and allocates
+    // one random element
+    for blocks in [10, 1000, 10000].iter() {
+        baseline.throughput(Throughput::Elements(1000));
+
+        baseline.bench_with_input(BenchmarkId::new("rust box", blocks), &blocks, {
+            |b, &s| {
+                let mut keep: Vec<_> = (0..*s * 64).map(|_| Some(Box::new(0u64))).collect();
+                b.iter(|| rust_box_churn(&mut keep, 1000));
+            }
+        });
+
+        baseline.bench_with_input(BenchmarkId::new("onsen box", blocks), &blocks, {
+            move |b, &s| {
+                let pool: onsen::RcPool<u64> = onsen::RcPool::new();
+                pool.with_growth_policy(onsen::GrowthPolicy::Fixed { entries: 64 });
+                let mut keep: Vec<_> = (0..*s * 64)
+                    .map(|_| Some(onsen::Box::new(0u64, &pool)))
+                    .collect();
+                b.iter(|| onsen_box_churn(&mut keep, 1000, &pool));
+            }
+        });
+    }
+
     drop(baseline);
 
     // The 5% and 95% cases turned out to be pretty close to the 50% case, thus disabled for now
@@ -2,8 +2,7 @@
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use std::time::Duration;
 
-use onsen;
-use onsen::PoolApi;
+use onsen::{FreelistStrategy, PoolApi};
 
 // The data we work on contains a primary value used for sorting and some payload which becomes mutated
 pub struct Data<const N: usize> {
@@ -63,11 +62,11 @@ trait Worker<'a> {
                 75..=79 => {
                     // sort the first 5% of the vec values increasing, none at the end
                     let pos = fast_prng(&mut state) as usize % (workspace.len() / 20);
-                    workspace[0..pos].sort_unstable_by(|a, b| {
-                        match (a, b) {
-                            (Some(a), Some(b)) => a.primary().partial_cmp(&b.primary()).unwrap(),
-                            _ => std::cmp::Ordering::Greater, // Contains a 'None'
-                        }
+                    workspace[0..pos].sort_unstable_by(|a, b| match (a, b) {
+                        (Some(a), Some(b)) => a.primary().cmp(b.primary()),
+                        (Some(_), None) => std::cmp::Ordering::Less,
+                        (None, Some(_)) => std::cmp::Ordering::Greater,
+                        (None, None) => std::cmp::Ordering::Equal,
                     });
                 }
                 80..=99 => {
@@ -108,11 +107,11 @@ trait Worker<'a> {
                 70..=74 => {
                     // sort the first 5% of the vec values increasing, none at the end
                     let pos = fast_prng(&mut state) as usize % (workspace.len() / 20);
-                    workspace[0..pos].sort_unstable_by(|a, b| {
-                        match (a, b) {
-                            (Some(a), Some(b)) => a.primary().partial_cmp(&b.primary()).unwrap(),
-                            _ => std::cmp::Ordering::Greater, // Contains a 'None'
-                        }
+                    workspace[0..pos].sort_unstable_by(|a, b| match (a, b) {
+                        (Some(a), Some(b)) => a.primary().cmp(b.primary()),
+                        (Some(_), None) => std::cmp::Ordering::Less,
+                        (None, Some(_)) => std::cmp::Ordering::Greater,
+                        (None, None) => std::cmp::Ordering::Equal,
                     });
                 }
                 75..=89 => {
@@ -133,8 +132,12 @@ trait Worker<'a> {
 }
 
 /// Owned data (copypaste, workaround the lack of GAT's, eventually needs macro or wait for GAT's)
+// the usize field is only there to make the owned data a bit bigger
+#[allow(dead_code)]
 pub struct SmallOwnedData(SmallData, usize);
+#[allow(dead_code)]
 pub struct MedOwnedData(MedData, usize);
+#[allow(dead_code)]
 pub struct BigOwnedData(BigData, usize);
 
 impl DataHandle for SmallOwnedData {
@@ -458,6 +461,89 @@ impl<'a> Worker<'a> for BigOnsenWorker {
     }
 }
 
+// data in a slot of a onsen pool with a given freelist strategy
+pub struct SlotData<'a, const N: usize, L: FreelistStrategy> {
+    slot: onsen::Slot<Data<N>, onsen::Mutable>,
+    pool: &'a onsen::Pool<Data<N>, L>,
+}
+
+impl<const N: usize, L: FreelistStrategy> DataHandle for SlotData<'_, N, L> {
+    fn primary(&self) -> &u32 {
+        &self.slot.get().primary
+    }
+
+    fn primary_mut(&mut self) -> &mut u32 {
+        &mut self.slot.get_mut().primary
+    }
+
+    fn payload(&mut self) -> &mut [u32] {
+        &mut self.slot.get_mut().payload
+    }
+}
+
+impl<const N: usize, L: FreelistStrategy> Drop for SlotData<'_, N, L> {
+    fn drop(&mut self) {
+        unsafe { self.pool.free_by_ref(&mut self.slot) };
+    }
+}
+
+// Worker for comparing the freelist strategies
+pub struct OnsenSlotWorker<const N: usize, L: FreelistStrategy> {
+    pool: onsen::Pool<Data<N>, L>,
+}
+
+impl<'a, const N: usize, L: FreelistStrategy + 'a> Worker<'a> for OnsenSlotWorker<N, L> {
+    type Data = SlotData<'a, N, L>;
+    fn new() -> Self {
+        let pool = onsen::Pool::with_strategy();
+        pool.with_min_entries(1000);
+        OnsenSlotWorker { pool }
+    }
+
+    fn new_element(&'a self, primary: u32) -> Option<Self::Data> {
+        Some(SlotData {
+            slot: self.pool.alloc(Data::new(primary)).for_mutation(),
+            pool: &self.pool,
+        })
+    }
+}
+
+fn bench_freelist_strategies<const N: usize>(c: &mut Criterion, name: &str) {
+    let mut simulated_work = c.benchmark_group(name);
+
+    for size in [100, 500, 1000, 3000, 5000, 7500, 10000].iter() {
+        simulated_work.throughput(Throughput::Elements(*size as u64));
+        simulated_work.measurement_time(Duration::from_secs(30));
+
+        simulated_work.bench_with_input(BenchmarkId::new("ordered", size), &size, {
+            |b, &s| {
+                let worker = OnsenSlotWorker::<N, onsen::Ordered>::new();
+                b.iter(|| {
+                    worker.run_drop(*s);
+                })
+            }
+        });
+
+        simulated_work.bench_with_input(BenchmarkId::new("lifo", size), &size, {
+            |b, &s| {
+                let worker = OnsenSlotWorker::<N, onsen::Lifo>::new();
+                b.iter(|| {
+                    worker.run_drop(*s);
+                })
+            }
+        });
+
+        simulated_work.bench_with_input(BenchmarkId::new("bitmap", size), &size, {
+            |b, &s| {
+                let worker = OnsenSlotWorker::<N, onsen::Bitmap>::new();
+                b.iter(|| {
+                    worker.run_drop(*s);
+                })
+            }
+        });
+    }
+}
+
 // Now implement the workers for onsen tbox
 pub struct SmallOnsenTBoxWorker;
 
@@ -778,6 +864,10 @@ fn criterion_benchmark(c: &mut Criterion) {
     }
 
     drop(simulated_work);
+
+    // Freelist strategy benchmarks
+    bench_freelist_strategies::<3>(c, "freelist strategies, small data");
+    bench_freelist_strategies::<64>(c, "freelist strategies, medium data");
 }
 
 criterion_group!(benches2, criterion_benchmark);
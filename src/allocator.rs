@@ -0,0 +1,104 @@
+use std::alloc::{alloc, dealloc, Layout};
+use std::ptr::NonNull;
+
+/// Provides the memory for the blocks of a pool. Pools use the `GlobalBlockAllocator` by
+/// default, other allocators are passed to the `with_allocator()` constructors of the
+/// pools. Since blocks are only allocated when a pool grows this is not performance
+/// critical.
+///
+/// # Safety
+///
+/// `alloc()` must return memory that satisfies the size and alignment of the requested
+/// layout and stays valid until it is given back with `dealloc()`.
+pub unsafe trait BlockAllocator: Sync {
+    /// Allocates memory for a block. Returns `None` when no memory is available.
+    fn alloc(&self, layout: Layout) -> Option<NonNull<u8>>;
+
+    /// Gives memory obtained from `alloc()` back.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be obtained from `alloc()` of the same allocator with the same `layout` and
+    /// must not be used afterwards.
+    unsafe fn dealloc(&self, ptr: NonNull<u8>, layout: Layout);
+
+    /// Returns true when huge pages were successfully requested for the memory at `ptr`
+    /// obtained from `alloc()`. This does not mean that the memory is actually backed by huge
+    /// pages, see `PoolStats::huge_page_advised_blocks`. Only used for the pool statistics,
+    /// the default returns false.
+    fn is_huge_page_advised(&self, _ptr: NonNull<u8>, _layout: Layout) -> bool {
+        false
+    }
+}
+
+/// Allocates blocks from the global rust allocator.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct GlobalBlockAllocator;
+
+unsafe impl BlockAllocator for GlobalBlockAllocator {
+    #[inline]
+    fn alloc(&self, layout: Layout) -> Option<NonNull<u8>> {
+        NonNull::new(unsafe { alloc(layout) })
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: NonNull<u8>, layout: Layout) {
+        dealloc(ptr.as_ptr(), layout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::alloc::Layout;
+    use std::ptr::NonNull;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingAllocator {
+        blocks: AtomicUsize,
+        bytes: AtomicUsize,
+    }
+
+    unsafe impl BlockAllocator for CountingAllocator {
+        fn alloc(&self, layout: Layout) -> Option<NonNull<u8>> {
+            self.blocks.fetch_add(1, Ordering::Relaxed);
+            self.bytes.fetch_add(layout.size(), Ordering::Relaxed);
+            GlobalBlockAllocator.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.blocks.fetch_sub(1, Ordering::Relaxed);
+            self.bytes.fetch_sub(layout.size(), Ordering::Relaxed);
+            GlobalBlockAllocator.dealloc(ptr, layout);
+        }
+    }
+
+    static COUNTING: CountingAllocator = CountingAllocator {
+        blocks: AtomicUsize::new(0),
+        bytes: AtomicUsize::new(0),
+    };
+
+    #[test]
+    fn counting() {
+        {
+            let pool: Pool<u64> = Pool::with_allocator(&COUNTING);
+            let mut slots = Vec::new();
+            for i in 0..1000 {
+                slots.push(pool.alloc(i));
+            }
+
+            let stats = pool.stats();
+            assert_eq!(
+                COUNTING.blocks.load(Ordering::Relaxed),
+                stats.blocks_allocated
+            );
+            assert_eq!(COUNTING.bytes.load(Ordering::Relaxed), stats.bytes_reserved);
+
+            unsafe {
+                slots.drain(..).for_each(|slot| pool.free(slot));
+            }
+        }
+        assert_eq!(COUNTING.blocks.load(Ordering::Relaxed), 0);
+        assert_eq!(COUNTING.bytes.load(Ordering::Relaxed), 0);
+    }
+}
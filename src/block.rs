@@ -1,6 +1,9 @@
-use std::alloc::{alloc, dealloc, Layout};
+use std::alloc::Layout;
+use std::collections::BTreeMap;
 use std::mem::size_of;
 use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
 
 use crate::*;
 
@@ -8,6 +11,41 @@ use crate::*;
 // makes sense.
 const MAX_ALIGN: usize = 1073741824usize;
 
+/// Marks entries in `Block::movables` that are not referenced by a movable.
+pub(crate) const NO_MOVABLE: u32 = u32::MAX;
+
+/// Serial number given to the next block allocated. Shared by all pools, thus a handle into
+/// memory that was reused by a block of another pool does not match its serial either.
+static NEXT_SERIAL: AtomicU32 = AtomicU32::new(0);
+
+/// Bitmap of the entries of a block that are not allocated, only kept when `POISONING` is
+/// enabled. The bits are atomic since slots of a `TPool` check them from other threads.
+struct FreedBits {
+    /// Address one past the last entry of the block.
+    end: usize,
+    entry_size: usize,
+    bits: Vec<AtomicU64>,
+}
+
+/// The freed bitmaps of all blocks keyed by the start address of their memory. Slots do not
+/// know the pool they belong to, they look up whether their entry is freed here.
+static FREED_REGISTRY: RwLock<BTreeMap<usize, Arc<FreedBits>>> = RwLock::new(BTreeMap::new());
+
+/// Returns true when `entry` is an entry of some block that is not allocated. Always false
+/// when `POISONING` is disabled.
+pub(crate) fn is_freed_entry(entry: *const u8) -> bool {
+    let addr = entry as usize;
+    let registry = FREED_REGISTRY.read().unwrap_or_else(|err| err.into_inner());
+    registry
+        .range(..=addr)
+        .next_back()
+        .filter(|(_, freed)| addr < freed.end)
+        .is_some_and(|(start, freed)| {
+            let index = (addr - start) / freed.entry_size;
+            freed.bits[index / 64].load(Ordering::Relaxed) & (1 << (index % 64)) != 0
+        })
+}
+
 /// The low level memory blocks and bitmaps.
 ///
 /// PANICS: One must not drop blocks while they are still in use. In debug mode this
@@ -16,49 +54,126 @@ const MAX_ALIGN: usize = 1073741824usize;
 /// use!
 ///
 /// In case fast application shutdown is important one can explicitly leak the memory.
-pub(crate) struct Block<T: Sized> {
+pub struct Block<T: Sized> {
     /// Pointer to an `[*mut Entry<T>, capacity]` with the first `len_used` entries in use.
     memory: NonNull<Entry<T>>,
     len_used: usize,
+    /// Number of entries in this block that are currently in use.
+    live: usize,
+    /// State of the freelist of this block, `None` when it is empty. Its meaning depends on
+    /// the `FreelistStrategy`.
+    pub(crate) freelist: Option<u32>,
+    /// Bitmap of the free entries, only used by the `Bitmap` strategy.
+    pub(crate) free: Vec<u64>,
+    /// True while the index of this block is on the stack of blocks with free entries of its
+    /// pool.
+    pub(crate) listed_free: bool,
+    /// Bitmap of the free entries whose memory was given back by `release_free_pages()`.
+    /// These are not linked into the freelist, their memory must not be touched. Allocated
+    /// on the first call of `release_free_pages()`.
+    released: Vec<u64>,
+    /// Number of entries in the `released` bitmap.
+    released_len: usize,
+    /// Bitmap of the entries that hold an initialized object.
+    initialized: Vec<u64>,
+    /// Generation counter of each entry, incremented whenever an entry is freed. Allocated
+    /// when the first handle to an entry of this block is created, entries without a counter
+    /// are at generation 0.
+    generations: Vec<u32>,
+    /// Index into the movables table of the pool for each entry, `NO_MOVABLE` when the entry
+    /// is not referenced by a movable. Allocated when the first movable of this block is
+    /// created.
+    movables: Vec<u32>,
+    /// Number of entries that are referenced by movables.
+    movables_len: usize,
+    /// The entries that are not allocated, registered in `FREED_REGISTRY`. `None` unless
+    /// `POISONING` is enabled.
+    freed: Option<Arc<FreedBits>>,
     capacity: usize,
+    /// Pool wide index of the first entry of this block.
+    base_index: usize,
+    /// Process wide unique number of this block, handles record it to detect when the memory
+    /// of a released block gets reused by a new block.
+    serial: u32,
     layout: Layout,
+    /// Huge pages were requested for the memory of this block.
+    huge_pages_advised: bool,
+    allocator: &'static dyn BlockAllocator,
 }
 
 impl<T: Sized> Block<T> {
-    // internal ctor
-    fn new(capacity: usize) -> Self {
-        let layout = Layout::array::<Entry<T>>(capacity).unwrap();
+    /// Allocates a block holding `capacity` entries from `allocator`. The entries are indexed
+    /// starting at `base_index`. The allocation is reported to `observer`.
+    pub(crate) fn new(
+        capacity: usize,
+        base_index: usize,
+        allocator: &'static dyn BlockAllocator,
+        observer: &impl PoolObserver,
+    ) -> Result<Self, AllocError> {
+        let layout = Layout::array::<Entry<T>>(capacity).map_err(|_| AllocError::LayoutOverflow)?;
+        let align = layout
+            .size()
+            .checked_next_power_of_two()
+            .map_or(MAX_ALIGN, |align| std::cmp::min(align, MAX_ALIGN));
         let layout = layout
-            .align_to(std::cmp::min(layout.size().next_power_of_two(), MAX_ALIGN))
-            .unwrap();
+            .align_to(align)
+            .map_err(|_| AllocError::LayoutOverflow)?;
 
-        let memory =
-            unsafe { NonNull::new(alloc(layout) as *mut Entry<T>).expect("Allocation failure") };
+        let words = capacity.div_ceil(64);
+        let mut initialized = Vec::new();
+        initialized
+            .try_reserve_exact(words)
+            .map_err(|_| AllocError::OutOfMemory)?;
+        initialized.resize(words, 0);
 
-        Self {
+        let memory = allocator.alloc(layout).ok_or(AllocError::OutOfMemory)?;
+        let huge_pages_advised = allocator.is_huge_page_advised(memory, layout);
+        let memory = memory.cast::<Entry<T>>();
+        let freed = (POISONING && !Entry::<T>::IS_ZST).then(|| {
+            // entries that were never handed out count as freed
+            let start = memory.as_ptr() as usize;
+            let freed = Arc::new(FreedBits {
+                end: start + capacity * size_of::<Entry<T>>(),
+                entry_size: size_of::<Entry<T>>(),
+                bits: (0..words).map(|_| AtomicU64::new(!0)).collect(),
+            });
+            FREED_REGISTRY
+                .write()
+                .unwrap_or_else(|err| err.into_inner())
+                .insert(start, Arc::clone(&freed));
+            freed
+        });
+        observer.on_block_alloc(capacity, layout.size());
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            entry_type = std::any::type_name::<T>(),
+            capacity,
+            bytes = layout.size(),
+            huge_pages_advised,
+            "block allocated"
+        );
+
+        Ok(Self {
             memory,
             len_used: 0,
+            live: 0,
+            freelist: None,
+            free: Vec::new(),
+            listed_free: false,
+            released: Vec::new(),
+            released_len: 0,
+            initialized,
+            generations: Vec::new(),
+            movables: Vec::new(),
+            movables_len: 0,
+            freed,
             capacity,
+            base_index,
+            serial: NEXT_SERIAL.fetch_add(1, Ordering::Relaxed),
             layout,
-        }
-    }
-
-    /// Create a new first block, takes `min_entries` as hint for the initial blocksize
-    /// calculation to contain at least this much entries.
-    pub(crate) fn new_first(min_entries: usize) -> Self {
-        let min_entries = std::cmp::max(64, min_entries);
-
-        // generous rounding to next power of two
-        let blocksize =
-            (min_entries * size_of::<Entry<T>>()).next_power_of_two() / size_of::<Entry<T>>();
-        Self::new(blocksize)
-    }
-
-    /// Create a sucessor block with twice the size than `self`.
-    pub(crate) fn new_next(&self) -> Self {
-        let blocksize =
-            (self.capacity * 2 * size_of::<Entry<T>>()).next_power_of_two() / size_of::<Entry<T>>();
-        Self::new(blocksize)
+            huge_pages_advised,
+            allocator,
+        })
     }
 
     /// Get a slice to the used part of the bitmap
@@ -71,6 +186,180 @@ impl<T: Sized> Block<T> {
         unsafe { std::slice::from_raw_parts_mut(self.memory.as_mut(), self.len_used) }
     }
 
+    /// Number of entries this block can hold.
+    #[inline]
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Pool wide index of the first entry of this block.
+    #[inline]
+    pub(crate) fn base_index(&self) -> usize {
+        self.base_index
+    }
+
+    /// Process wide unique number of this block.
+    #[inline]
+    pub(crate) fn serial(&self) -> u32 {
+        self.serial
+    }
+
+    /// Number of entries that were ever handed out from this block.
+    #[inline]
+    pub(crate) fn len_used(&self) -> usize {
+        self.len_used
+    }
+
+    /// Size in bytes of the memory allocated for this block.
+    #[inline]
+    pub(crate) fn size(&self) -> usize {
+        self.layout.size()
+    }
+
+    /// Returns true when huge pages were requested for the memory of this block.
+    #[inline]
+    pub(crate) fn is_huge_page_advised(&self) -> bool {
+        self.huge_pages_advised
+    }
+
+    /// Number of entries of this block that are currently in use.
+    #[inline]
+    pub(crate) fn live(&self) -> usize {
+        self.live
+    }
+
+    /// Accounts for an entry that was given back to this block.
+    #[inline]
+    pub(crate) fn dec_live(&mut self) {
+        debug_assert!(self.live > 0);
+        self.live -= 1;
+    }
+
+    /// Returns true when this block has free entries to be reused.
+    #[inline]
+    pub(crate) fn has_free(&self) -> bool {
+        self.freelist.is_some() || self.released_len > 0
+    }
+
+    /// Takes an entry from the freelist of this block. When the freelist is empty the lowest
+    /// released entry is taken.
+    pub(crate) fn pop_free<L: FreelistStrategy>(&mut self) -> Option<NonNull<Entry<T>>> {
+        let index = if let Some(index) = L::pop(self) {
+            index
+        } else {
+            let index = next_set_bit(&self.released, 0)?;
+            clear_bit(&mut self.released, index);
+            self.released_len -= 1;
+            index
+        };
+        debug_assert!(self.live < self.len_used);
+        self.live += 1;
+        self.clear_freed(index);
+        Some(self.entry_at(index))
+    }
+
+    /// Gives the memory of all pages of this block that contain only free entries back to the
+    /// operating system. The entries on these pages are removed from the freelist and kept
+    /// in the `released` bitmap. Returns the number of bytes released.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn release_free_pages<L: FreelistStrategy>(&mut self, page_size: usize) -> usize {
+        if self.freelist.is_none() {
+            return 0;
+        }
+
+        // take all entries out of the freelist, the remaining ones are pushed back later
+        let words = self.capacity.div_ceil(64);
+        if self.released.is_empty() {
+            self.released.resize(words, 0);
+        }
+        let mut free = vec![0u64; words];
+        L::drain(self, &mut free);
+
+        let entry_size = size_of::<Entry<T>>();
+        let start = self.start() as usize;
+        let end = start + self.capacity * entry_size;
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        let mut page = start.next_multiple_of(page_size);
+        while page + page_size <= end {
+            let entries = (page - start) / entry_size..=(page + page_size - 1 - start) / entry_size;
+            let is_free = |index: usize| {
+                index >= self.len_used || test_bit(&free, index) || test_bit(&self.released, index)
+            };
+
+            // only pages with entries on the freelist are new to release
+            if entries.clone().all(is_free) && entries.clone().any(|index| test_bit(&free, index)) {
+                for index in entries.filter(|&index| test_bit(&free, index)) {
+                    if !test_bit(&self.released, index) {
+                        set_bit(&mut self.released, index);
+                        self.released_len += 1;
+                    }
+                }
+                match runs.last_mut() {
+                    Some((run_start, run_len)) if *run_start + *run_len == page => {
+                        *run_len += page_size;
+                    }
+                    _ => runs.push((page, page_size)),
+                }
+            }
+            page += page_size;
+        }
+
+        // rebuild the freelist in ascending order from the entries that are not released,
+        // this must be done before the memory is given back
+        let mut index = 0;
+        while let Some(next) = next_set_bit(&free, index) {
+            if !test_bit(&self.released, next) {
+                unsafe { L::push_ascending(self, next) };
+            }
+            index = next + 1;
+        }
+
+        runs.iter()
+            .filter(|(addr, len)| unsafe {
+                libc::madvise(*addr as *mut libc::c_void, *len, libc::MADV_DONTNEED) == 0
+            })
+            .map(|(_, len)| len)
+            .sum()
+    }
+
+    /// Puts the entry at `index` back into the freelist of this block.
+    ///
+    /// # Safety
+    ///
+    /// The entry must be in use and no references to it must exist.
+    #[inline]
+    pub(crate) unsafe fn push_free<L: FreelistStrategy>(&mut self, index: usize) {
+        L::push(self, index);
+    }
+
+    /// Puts the entry at `index` back into the freelist of this block, used for runs of
+    /// entries in ascending order.
+    ///
+    /// # Safety
+    ///
+    /// The entry must be in use and no references to it must exist.
+    #[inline]
+    pub(crate) unsafe fn push_free_ascending<L: FreelistStrategy>(&mut self, index: usize) {
+        L::push_ascending(self, index);
+    }
+
+    /// Returns the indices of the `Ordered` freelist of this block starting at its head.
+    #[cfg(test)]
+    pub(crate) fn freelist(&self) -> Vec<usize> {
+        let mut indices = Vec::new();
+        if let Some(head) = self.freelist {
+            let mut index = head;
+            loop {
+                indices.push(index as usize);
+                index = unsafe { Entry::next(self.start(), index) };
+                if index == head {
+                    break;
+                }
+            }
+        }
+        indices
+    }
+
     /// returns true when a blocks capacity is exhausted
     #[inline]
     pub(crate) fn is_full(&self) -> bool {
@@ -82,10 +371,169 @@ impl<T: Sized> Block<T> {
         debug_assert!(self.len_used < self.capacity);
         let pos = self.len_used;
         self.len_used += 1;
+        self.live += 1;
+        self.clear_freed(pos);
         // Safety: checked len_used < capacity
         unsafe { NonNull::new_unchecked(self.entries_mut().get_unchecked_mut(pos)) }
     }
 
+    /// Address of the first entry of this block.
+    #[inline]
+    pub(crate) fn start(&self) -> *mut Entry<T> {
+        self.memory.as_ptr()
+    }
+
+    /// Index of `entry` within this block, `entry` must belong to this block.
+    #[inline]
+    pub(crate) fn index_of(&self, entry: *mut Entry<T>) -> usize {
+        debug_assert!(self.contains_entry(entry));
+        unsafe { entry.offset_from(self.start()) as usize }
+    }
+
+    /// Pointer to the entry at `index`.
+    #[inline]
+    pub(crate) fn entry_at(&self, index: usize) -> NonNull<Entry<T>> {
+        debug_assert!(index < self.len_used);
+        unsafe { NonNull::new_unchecked(self.start().add(index)) }
+    }
+
+    /// Marks the entry at `index` as holding an initialized object.
+    #[inline]
+    pub(crate) fn set_initialized(&mut self, index: usize) {
+        set_bit(&mut self.initialized, index);
+    }
+
+    /// Clears the initialized mark of the entry at `index`.
+    #[inline]
+    pub(crate) fn clear_initialized(&mut self, index: usize) {
+        clear_bit(&mut self.initialized, index);
+    }
+
+    /// Returns true when the entry at `index` holds an initialized object.
+    #[inline]
+    pub(crate) fn is_initialized(&self, index: usize) -> bool {
+        test_bit(&self.initialized, index)
+    }
+
+    /// Allocates the generation counters of this block unless this was done before.
+    pub(crate) fn track_generations(&mut self) -> Result<(), AllocError> {
+        if self.generations.is_empty() {
+            self.generations
+                .try_reserve_exact(self.capacity)
+                .map_err(|_| AllocError::OutOfMemory)?;
+            self.generations.resize(self.capacity, 0);
+        }
+        Ok(())
+    }
+
+    /// Returns true when the generation counters of this block are allocated.
+    #[inline]
+    pub(crate) fn tracks_generations(&self) -> bool {
+        !self.generations.is_empty()
+    }
+
+    /// Generation of the entry at `index`.
+    #[inline]
+    pub(crate) fn generation(&self, index: usize) -> u32 {
+        self.generations.get(index).copied().unwrap_or(0)
+    }
+
+    /// Starts a new generation for the entry at `index`. No-op while no handles to this block
+    /// were created.
+    #[inline]
+    pub(crate) fn inc_generation(&mut self, index: usize) {
+        if let Some(generation) = self.generations.get_mut(index) {
+            *generation = generation.wrapping_add(1);
+        }
+    }
+
+    /// Marks the entry at `index` as not allocated. No-op unless `POISONING` is enabled.
+    #[inline]
+    pub(crate) fn set_freed(&self, index: usize) {
+        if let Some(freed) = &self.freed {
+            freed.bits[index / 64].fetch_or(1 << (index % 64), Ordering::Relaxed);
+        }
+    }
+
+    /// Returns true when the entry at `index` is not allocated. Always false unless
+    /// `POISONING` is enabled.
+    #[inline]
+    pub(crate) fn is_freed(&self, index: usize) -> bool {
+        self.freed.as_ref().is_some_and(|freed| {
+            freed.bits[index / 64].load(Ordering::Relaxed) & (1 << (index % 64)) != 0
+        })
+    }
+
+    /// Marks the entry at `index` as allocated. No-op unless `POISONING` is enabled.
+    #[inline]
+    fn clear_freed(&self, index: usize) {
+        if let Some(freed) = &self.freed {
+            freed.bits[index / 64].fetch_and(!(1 << (index % 64)), Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the index into the movables table of the entry at `index` when it is
+    /// referenced by a movable.
+    #[inline]
+    pub(crate) fn movable(&self, index: usize) -> Option<u32> {
+        self.movables
+            .get(index)
+            .copied()
+            .filter(|&movable| movable != NO_MOVABLE)
+    }
+
+    /// Records that the entry at `index` is referenced by the movables table entry `movable`.
+    pub(crate) fn set_movable(&mut self, index: usize, movable: u32) {
+        if self.movables.is_empty() {
+            self.movables.resize(self.capacity, NO_MOVABLE);
+        }
+        debug_assert_eq!(self.movables[index], NO_MOVABLE);
+        self.movables[index] = movable;
+        self.movables_len += 1;
+    }
+
+    /// Forgets about the movable referencing the entry at `index` and returns its index into
+    /// the movables table.
+    #[inline]
+    pub(crate) fn clear_movable(&mut self, index: usize) -> Option<u32> {
+        let movable = self.movable(index)?;
+        self.movables[index] = NO_MOVABLE;
+        self.movables_len -= 1;
+        Some(movable)
+    }
+
+    /// Number of entries of this block that are referenced by movables.
+    #[inline]
+    pub(crate) fn movables_len(&self) -> usize {
+        self.movables_len
+    }
+
+    /// Returns the index of the first initialized entry at or after `from`.
+    pub(crate) fn next_initialized(&self, from: usize) -> Option<usize> {
+        next_set_bit(&self.initialized, from)
+    }
+
+    /// Forgets about all entries, the block becomes unused again. Does not drop any objects.
+    /// Generations are kept, handles to entries of this block stay stale.
+    pub(crate) fn reset(&mut self) {
+        self.len_used = 0;
+        self.live = 0;
+        self.freelist = None;
+        self.free.fill(0);
+        self.listed_free = false;
+        self.released.fill(0);
+        self.released_len = 0;
+        self.initialized.fill(0);
+        self.movables.fill(NO_MOVABLE);
+        self.movables_len = 0;
+        if let Some(freed) = &self.freed {
+            freed
+                .bits
+                .iter()
+                .for_each(|word| word.store(!0, Ordering::Relaxed));
+        }
+    }
+
     /// returns true when entry belongs to self
     pub(crate) fn contains_entry(&self, entry: *mut Entry<T>) -> bool {
         self.entries()
@@ -94,9 +542,53 @@ impl<T: Sized> Block<T> {
     }
 }
 
+#[inline]
+pub(crate) fn set_bit(bitmap: &mut [u64], index: usize) {
+    bitmap[index / 64] |= 1 << (index % 64);
+}
+
+#[inline]
+pub(crate) fn clear_bit(bitmap: &mut [u64], index: usize) {
+    bitmap[index / 64] &= !(1 << (index % 64));
+}
+
+#[inline]
+pub(crate) fn test_bit(bitmap: &[u64], index: usize) -> bool {
+    bitmap[index / 64] & (1 << (index % 64)) != 0
+}
+
+/// Returns the index of the first set bit at or after `from`.
+pub(crate) fn next_set_bit(bitmap: &[u64], from: usize) -> Option<usize> {
+    let mut word = from / 64;
+    let mut bits = *bitmap.get(word)? & (!0 << (from % 64));
+    loop {
+        if bits != 0 {
+            return Some(word * 64 + bits.trailing_zeros() as usize);
+        }
+        word += 1;
+        bits = *bitmap.get(word)?;
+    }
+}
+
 impl<T> Drop for Block<T> {
     fn drop(&mut self) {
-        unsafe { dealloc(self.memory.as_ptr() as *mut u8, self.layout) };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            entry_type = std::any::type_name::<T>(),
+            capacity = self.capacity,
+            bytes = self.layout.size(),
+            "block freed"
+        );
+        if self.freed.is_some() {
+            FREED_REGISTRY
+                .write()
+                .unwrap_or_else(|err| err.into_inner())
+                .remove(&(self.memory.as_ptr() as usize));
+        }
+        unsafe {
+            self.allocator
+                .dealloc(self.memory.cast::<u8>(), self.layout);
+        }
     }
 }
 
@@ -105,8 +597,15 @@ impl<T> fmt::Debug for Block<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         f.debug_struct("Block")
             .field("len_used", &self.len_used)
+            .field("live", &self.live)
+            .field("freelist", &self.freelist)
+            .field("released_len", &self.released_len)
+            .field("movables_len", &self.movables_len)
             .field("capacity", &self.capacity)
+            .field("base_index", &self.base_index)
+            .field("serial", &self.serial)
             .field("layout", &self.layout)
+            .field("huge_pages_advised", &self.huge_pages_advised)
             .finish()
     }
 }
@@ -117,7 +616,52 @@ mod tests {
 
     #[test]
     fn smoke() {
-        let block: Block<String> = Block::new_first(0);
-        let _block2 = Block::new_next(&block);
+        let _block: Block<String> = Block::new(64, 0, &GlobalBlockAllocator, &NoObserver).unwrap();
+    }
+
+    #[test]
+    fn initialized_bitmap() {
+        let mut block: Block<u64> = Block::new(200, 0, &GlobalBlockAllocator, &NoObserver).unwrap();
+        for _ in 0..200 {
+            block.extend();
+        }
+        assert_eq!(block.next_initialized(0), None);
+
+        block.set_initialized(3);
+        block.set_initialized(64);
+        block.set_initialized(199);
+        assert_eq!(block.next_initialized(0), Some(3));
+        assert_eq!(block.next_initialized(4), Some(64));
+        assert_eq!(block.next_initialized(65), Some(199));
+        assert_eq!(block.next_initialized(200), None);
+
+        block.clear_initialized(64);
+        assert_eq!(block.next_initialized(4), Some(199));
+        assert_eq!(block.index_of(block.entry_at(199).as_ptr()), 199);
+
+        block.reset();
+        assert_eq!(block.next_initialized(0), None);
+    }
+
+    #[test]
+    fn lazy_generations() {
+        let mut block: Block<u64> = Block::new(64, 0, &GlobalBlockAllocator, &NoObserver).unwrap();
+        block.extend();
+
+        // generations are only counted once handles are used
+        block.inc_generation(0);
+        assert_eq!(block.generation(0), 0);
+        block.track_generations().unwrap();
+        block.inc_generation(0);
+        assert_eq!(block.generation(0), 1);
+    }
+
+    #[test]
+    fn layout_overflow() {
+        assert_eq!(
+            Block::<String>::new(usize::MAX / 2, 0, &GlobalBlockAllocator, &NoObserver)
+                .unwrap_err(),
+            AllocError::LayoutOverflow
+        );
     }
 }
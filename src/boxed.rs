@@ -37,6 +37,34 @@ impl<T> Box<T> {
         }
     }
 
+    /// Fallible variant of `Box::new()`. Returns an `AllocError` when the pool can not
+    /// allocate an entry.
+    #[inline]
+    pub fn try_new(t: T, pool: impl AsRef<RcPool<T>>) -> Result<Self, AllocError> {
+        Ok(Self {
+            slot: pool.as_ref().try_alloc(t)?.for_mutation(),
+            pool: pool.as_ref().clone(),
+        })
+    }
+
+    /// Allocate a Box from a `RcPool` and initialize it with the value returned by `f`. The
+    /// value is written directly into the pool, this allows the compiler to construct it in
+    /// place, see `PoolApi::alloc_with()`.
+    ///
+    /// ```
+    /// use onsen::*;
+    ///
+    /// let pool: RcPool<[u8; 4096]> = RcPool::new();
+    /// let mybox = Box::new_with(|| [0; 4096], &pool);
+    /// ```
+    #[inline]
+    pub fn new_with<F: FnOnce() -> T>(f: F, pool: impl AsRef<RcPool<T>>) -> Self {
+        Self {
+            slot: pool.as_ref().alloc_with(f).for_mutation(),
+            pool: pool.as_ref().clone(),
+        }
+    }
+
     /// Associated function that frees the memory of a Box without calling the destructor of
     /// its value.
     #[inline]
@@ -244,4 +272,15 @@ mod tests {
         let pool: RcPool<&str> = RcPool::new();
         let _mybox = Box::new("Boxed", &pool);
     }
+
+    #[test]
+    fn try_new() {
+        let pool: RcPool<&str> = RcPool::new();
+        let mybox = Box::try_new("Boxed", &pool).unwrap();
+        assert_eq!(*mybox, "Boxed");
+
+        let pool: RcPool<&str> = RcPool::new();
+        pool.with_min_entries(usize::MAX / 2);
+        assert!(Box::try_new("Boxed", &pool).is_err());
+    }
 }
@@ -1,12 +1,33 @@
-use std::mem::ManuallyDrop;
+use std::mem::{align_of, size_of, ManuallyDrop};
+use std::ops::Range;
 use std::ptr::NonNull;
 
-/// The type of the freelist node. When used (node is free) then this is a cyclic list with
-/// pointers *always* pointing to some valid entry (pointing to itself when this is the only
-/// node in the list).
-pub(crate) struct FreelistNode<T> {
-    pub prev: *mut Entry<T>,
-    pub next: *mut Entry<T>,
+/// Alignment and size of the memory shared by all entries of zero sized types.
+const ZST_ALIGN: usize = 4096;
+
+#[repr(C, align(4096))]
+struct ZstMemory([u8; ZST_ALIGN]);
+
+/// All entries of zero sized types point here. Nothing is ever written to this memory, it
+/// only provides a valid address for references to the entry.
+static mut ZST_MEMORY: ZstMemory = ZstMemory([0; ZST_ALIGN]);
+
+/// True when freed entries are filled with the `POISON` pattern and blocks track which of
+/// their entries are freed, this is done in debug builds or with the `poison` feature.
+pub(crate) const POISONING: bool = cfg!(any(debug_assertions, feature = "poison"));
+
+/// The pattern freed entries are filled with.
+const POISON: [u8; 4] = [0xDE, 0xAD, 0xBE, 0xEF];
+
+/// The type of the freelist node. Every block has its own freelist, the links are indices of
+/// entries within the block. This keeps the node at 8 bytes, thus entries of small types are
+/// not larger than 8 bytes. When used (node is free) then this is a cyclic list with indices
+/// *always* referring to some free entry of the block (referring to itself when this is the
+/// only node in the list).
+#[derive(Copy, Clone)]
+pub(crate) struct FreelistNode {
+    pub prev: u32,
+    pub next: u32,
 }
 
 /// Entries within a Pool. This can either hold user data (potentially uninitialized) or the
@@ -14,7 +35,7 @@ pub(crate) struct FreelistNode<T> {
 #[repr(align(8))]
 pub union Entry<T> {
     pub(crate) data: ManuallyDrop<T>,
-    pub(crate) freelist_node: ManuallyDrop<FreelistNode<T>>,
+    pub(crate) freelist_node: FreelistNode,
 }
 
 unsafe impl<T: Send> Send for Entry<T> {}
@@ -24,12 +45,35 @@ unsafe impl<T: Sync> Sync for Entry<T> {}
 //          pub(crate) unsafe fn foo(self: *mut Self)
 
 impl<T> Entry<T> {
+    /// True when `T` is a zero sized type that gets no memory within a block. Zero sized
+    /// types with an alignment larger than 4096 are stored in blocks like any other type.
+    pub(crate) const IS_ZST: bool =
+        size_of::<T>() == 0 && size_of::<Self>() <= ZST_ALIGN && align_of::<Self>() <= ZST_ALIGN;
+
+    /// The bytes of an entry that are poisoned when it is freed, all bytes after the freelist
+    /// node.
+    const POISON_RANGE: Range<usize> = size_of::<FreelistNode>()..size_of::<Self>();
+
+    /// The entry shared by all allocations of a zero sized type.
+    #[inline]
+    pub(crate) fn zst() -> NonNull<Self> {
+        debug_assert!(Self::IS_ZST);
+        unsafe { NonNull::new_unchecked(std::ptr::addr_of_mut!(ZST_MEMORY).cast::<Self>()) }
+    }
+
+    /// Returns a pointer to the data of the entry `this` without going through a reference to
+    /// the whole entry. `Entry<T>` is never zero sized, but all entries of zero sized types
+    /// share the same memory, thus only references to their data may be created.
+    #[inline(always)]
+    pub(crate) fn data_ptr(this: NonNull<Self>) -> *mut ManuallyDrop<T> {
+        unsafe { std::ptr::addr_of_mut!((*this.as_ptr()).data) }
+    }
+
     /// Overwrites the potentially uninitialized `Entry` with new data without dropping the
     /// old value. Returns a reference to the new data. This is safe because rust does not
     /// give the guarantees that destructors are always called. Still there is a danger
-    /// because of this that some resources may be leaked. The onsen API ensures that the user
-    /// only ever gets an Entry reference when this is not a linked freelist node, actually
-    /// only the `Slot::get_uninit()` will expose an &mut Entry which makes this safe.
+    /// because of this that some resources may be leaked. The onsen API never hands out an
+    /// &mut Entry, entries are only exposed as raw pointers, which makes this safe.
     #[inline(always)]
     pub fn write(&mut self, val: T) -> &mut T {
         self.data = ManuallyDrop::new(val);
@@ -37,83 +81,103 @@ impl<T> Entry<T> {
         unsafe { &mut self.data }
     }
 
-    /// Removes an entry from the freelist and returns the entry that was next to self, if any.
-    pub(crate) unsafe fn remove_free_node(&mut self) -> Option<NonNull<Entry<T>>> {
-        let next = self.freelist_node.next;
-        if next == self {
+    /// Fills the bytes of the freed entry `this` after its freelist node with the poison
+    /// pattern.
+    pub(crate) unsafe fn poison(this: *mut Self) {
+        let bytes = this.cast::<u8>();
+        for i in Self::POISON_RANGE {
+            bytes.add(i).write(POISON[i % POISON.len()]);
+        }
+    }
+
+    /// Removes the entry at index `this` of the block starting at `base` from the freelist
+    /// and returns the index of the entry that was next to it, if any.
+    pub(crate) unsafe fn remove_free_node(base: *mut Self, this: u32) -> Option<u32> {
+        let next = Entry::next(base, this);
+        if next == this {
             // single node in list, nothing need to be done.
             None
         } else {
             // unlink from list
-            let prev = self.freelist_node.prev;
-            Entry::set_next(prev, next);
-            Entry::set_prev(next, prev);
-
-            Some(NonNull::new_unchecked(
-                // decide which side to return as new freelist head
-                if (next as usize + prev as usize) / 2 < self as *const Self as usize {
-                    prev
-                } else {
-                    next
-                },
-            ))
+            let prev = Entry::prev(base, this);
+            Entry::set_next(base, prev, next);
+            Entry::set_prev(base, next, prev);
+
+            // decide which side to return as new freelist head
+            Some(if (next as u64 + prev as u64) / 2 < this as u64 {
+                prev
+            } else {
+                next
+            })
         }
     }
 
-    /// Initializes the freelist node to be pointing to itself.
-    pub(crate) unsafe fn init_free_node(this: *mut Self) {
-        Entry::set_next(this, this);
-        Entry::set_prev(this, this);
+    /// Initializes the freelist node at index `this` to be pointing to itself.
+    pub(crate) unsafe fn init_free_node(base: *mut Self, this: u32) {
+        Entry::set_next(base, this, this);
+        Entry::set_prev(base, this, this);
     }
 
-    /// Partial ordered insert if a freed node into the freelist. Order is determined by address of
-    /// given nodes. The `freed_node` is either inserted before or after 'this'.
-    pub(crate) unsafe fn insert_free_node(mut this: *mut Self, freed_node: *mut Self) {
+    /// Partial ordered insert if a freed node into the freelist. Order is determined by the
+    /// index of the given nodes. The `freed_node` is either inserted before or after 'this'.
+    pub(crate) unsafe fn insert_free_node(base: *mut Self, mut this: u32, freed_node: u32) {
         if freed_node < this {
             // insert freed_node before this
 
             // one more sorting step has no measurable impact on performance but may lead to
             // better cache locality
-            if freed_node < Entry::prev(this) {
-                this = Entry::prev(this);
+            if freed_node < Entry::prev(base, this) {
+                this = Entry::prev(base, this);
             }
 
-            Entry::set_next(freed_node, this);
-            Entry::set_prev(freed_node, Entry::prev(this));
-            Entry::set_next(Entry::prev(this), freed_node);
-            Entry::set_prev(this, freed_node);
+            let prev = Entry::prev(base, this);
+            Entry::set_next(base, freed_node, this);
+            Entry::set_prev(base, freed_node, prev);
+            Entry::set_next(base, prev, freed_node);
+            Entry::set_prev(base, this, freed_node);
         } else {
             // insert freed_node after this
 
-            if freed_node > Entry::next(this) {
-                this = Entry::next(this);
+            if freed_node > Entry::next(base, this) {
+                this = Entry::next(base, this);
             }
 
-            Entry::set_prev(freed_node, this);
-            Entry::set_next(freed_node, Entry::next(this));
-            Entry::set_prev(Entry::next(this), freed_node);
-            Entry::set_next(this, freed_node);
+            Entry::link_free_node_after(base, this, freed_node);
         }
     }
 
+    /// Links a freed node into the freelist directly after 'this', without any ordering.
+    pub(crate) unsafe fn link_free_node_after(base: *mut Self, this: u32, freed_node: u32) {
+        let next = Entry::next(base, this);
+        Entry::set_prev(base, freed_node, this);
+        Entry::set_next(base, freed_node, next);
+        Entry::set_prev(base, next, freed_node);
+        Entry::set_next(base, this, freed_node);
+    }
+
+    #[inline(always)]
+    unsafe fn node(base: *mut Self, this: u32) -> *mut FreelistNode {
+        std::ptr::addr_of_mut!((*base.add(this as usize)).freelist_node)
+    }
+
     #[inline(always)]
-    unsafe fn next(this: *mut Self) -> *mut Self {
-        (*(*this).freelist_node).next
+    pub(crate) unsafe fn next(base: *mut Self, this: u32) -> u32 {
+        (*Entry::node(base, this)).next
     }
 
     #[inline(always)]
-    unsafe fn prev(this: *mut Self) -> *mut Self {
-        (*(*this).freelist_node).prev
+    unsafe fn prev(base: *mut Self, this: u32) -> u32 {
+        (*Entry::node(base, this)).prev
     }
 
     #[inline(always)]
-    unsafe fn set_next(this: *mut Self, that: *mut Self) {
-        (*(*this).freelist_node).next = that;
+    pub(crate) unsafe fn set_next(base: *mut Self, this: u32, that: u32) {
+        (*Entry::node(base, this)).next = that;
     }
 
     #[inline(always)]
-    unsafe fn set_prev(this: *mut Self, that: *mut Self) {
-        (*(*this).freelist_node).prev = that;
+    unsafe fn set_prev(base: *mut Self, this: u32, that: u32) {
+        (*Entry::node(base, this)).prev = that;
     }
 }
 
@@ -128,3 +192,11 @@ fn entry_layout() {
         (&e) as *const Entry<String> as usize
     );
 }
+
+#[test]
+fn entry_size() {
+    assert_eq!(size_of::<Entry<u8>>(), 8);
+    assert_eq!(size_of::<Entry<u32>>(), 8);
+    assert_eq!(size_of::<Entry<u64>>(), 8);
+    assert_eq!(size_of::<Entry<[u8; 12]>>(), 16);
+}
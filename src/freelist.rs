@@ -0,0 +1,227 @@
+use crate::*;
+
+/// How the free entries of a block are kept and which one is reused first. Pools take the
+/// strategy as type parameter which defaults to `Ordered`. The strategies differ only in
+/// performance and locality, the pool behaves the same with any of them.
+///
+/// ```
+/// use onsen::*;
+///
+/// let pool: Pool<u64, Lifo> = Pool::with_strategy();
+/// let slot = pool.alloc(1234);
+/// unsafe { pool.free(slot) };
+/// ```
+pub trait FreelistStrategy: 'static {
+    /// Takes an entry from the freelist of `block` and returns its index.
+    #[doc(hidden)]
+    fn pop<T>(block: &mut Block<T>) -> Option<usize>;
+
+    /// Puts the free entry at `index` into the freelist of `block`.
+    ///
+    /// # Safety
+    ///
+    /// The entry must belong to the block and must not be in the freelist.
+    #[doc(hidden)]
+    unsafe fn push<T>(block: &mut Block<T>, index: usize);
+
+    /// Like `push()`, used when entries are pushed in ascending order, `index` is higher
+    /// than the index pushed before.
+    ///
+    /// # Safety
+    ///
+    /// Same as `push()`.
+    #[doc(hidden)]
+    #[inline]
+    unsafe fn push_ascending<T>(block: &mut Block<T>, index: usize) {
+        Self::push(block, index);
+    }
+
+    /// Sets the bits of all entries of the freelist of `block` in `free` and empties the
+    /// freelist.
+    #[doc(hidden)]
+    fn drain<T>(block: &mut Block<T>, free: &mut [u64]);
+}
+
+/// Keeps the free entries in a weakly address ordered doubly linked cyclic list. The entry
+/// reused next is always close to the last freed one. This gives good locality for most
+/// workloads and is the default.
+pub enum Ordered {}
+
+impl FreelistStrategy for Ordered {
+    #[inline]
+    fn pop<T>(block: &mut Block<T>) -> Option<usize> {
+        let head = block.freelist?;
+        block.freelist = unsafe { Entry::remove_free_node(block.start(), head) };
+        Some(head as usize)
+    }
+
+    #[inline]
+    unsafe fn push<T>(block: &mut Block<T>, index: usize) {
+        let index = index as u32;
+        if let Some(head) = block.freelist {
+            Entry::insert_free_node(block.start(), head, index);
+        } else {
+            Entry::init_free_node(block.start(), index);
+        }
+        block.freelist = Some(index);
+    }
+
+    #[inline]
+    unsafe fn push_ascending<T>(block: &mut Block<T>, index: usize) {
+        if let Some(head) = block.freelist {
+            let index = index as u32;
+            Entry::link_free_node_after(block.start(), head, index);
+            block.freelist = Some(index);
+        } else {
+            Self::push(block, index);
+        }
+    }
+
+    fn drain<T>(block: &mut Block<T>, free: &mut [u64]) {
+        if let Some(head) = block.freelist.take() {
+            let mut index = head;
+            loop {
+                set_bit(free, index as usize);
+                index = unsafe { Entry::next(block.start(), index) };
+                if index == head {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Keeps the free entries in a singly linked stack. The entry freed last is reused first.
+/// This is the fastest strategy, but it does not care about the order of the entries.
+pub enum Lifo {}
+
+impl FreelistStrategy for Lifo {
+    #[inline]
+    fn pop<T>(block: &mut Block<T>) -> Option<usize> {
+        let head = block.freelist?;
+        // the bottom of the stack links to itself
+        let next = unsafe { Entry::next(block.start(), head) };
+        block.freelist = (next != head).then_some(next);
+        Some(head as usize)
+    }
+
+    #[inline]
+    unsafe fn push<T>(block: &mut Block<T>, index: usize) {
+        let index = index as u32;
+        Entry::set_next(block.start(), index, block.freelist.unwrap_or(index));
+        block.freelist = Some(index);
+    }
+
+    fn drain<T>(block: &mut Block<T>, free: &mut [u64]) {
+        let mut next = block.freelist.take();
+        while let Some(index) = next {
+            set_bit(free, index as usize);
+            let link = unsafe { Entry::next(block.start(), index) };
+            next = (link != index).then_some(link);
+        }
+    }
+}
+
+/// Keeps the free entries in a bitmap per block and always reuses the free entry with the
+/// lowest address. Gives the best locality when entries are freed in random order. The
+/// memory of free entries is never touched.
+pub enum Bitmap {}
+
+impl FreelistStrategy for Bitmap {
+    #[inline]
+    fn pop<T>(block: &mut Block<T>) -> Option<usize> {
+        // the freelist is the lowest free index
+        let index = block.freelist? as usize;
+        clear_bit(&mut block.free, index);
+        block.freelist = next_set_bit(&block.free, index + 1).map(|next| next as u32);
+        Some(index)
+    }
+
+    #[inline]
+    unsafe fn push<T>(block: &mut Block<T>, index: usize) {
+        if block.free.is_empty() {
+            block.free.resize(block.capacity().div_ceil(64), 0);
+        }
+        set_bit(&mut block.free, index);
+        let index = index as u32;
+        block.freelist = Some(block.freelist.map_or(index, |lowest| lowest.min(index)));
+    }
+
+    fn drain<T>(block: &mut Block<T>, free: &mut [u64]) {
+        if block.freelist.take().is_some() {
+            for (free, bits) in free.iter_mut().zip(block.free.iter_mut()) {
+                *free |= std::mem::take(bits);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn strategy<L: FreelistStrategy>() {
+        let pool: Pool<u64, L> = Pool::with_strategy();
+        pool.with_growth_policy(GrowthPolicy::Fixed { entries: 1000 });
+        let mut slots: Vec<_> = (0..3000).map(|i| pool.alloc(i)).collect();
+
+        // free every third entry in descending order
+        let mut freed = Vec::new();
+        for i in (0..3000).rev().filter(|i| i % 3 == 0) {
+            freed.push(slots[i].get() as *const u64);
+            unsafe { pool.free(slots.swap_remove(i)) };
+        }
+        assert_eq!(pool.stats().freelist_len, 1000);
+
+        // all freed entries are reused before the pool grows
+        let mut reused: Vec<_> = (0..1000).map(|i| pool.alloc(i)).collect();
+        let stats = pool.stats();
+        assert_eq!(stats.freelist_len, 0);
+        assert_eq!(stats.blocks_allocated, 3);
+        let mut addrs: Vec<_> = reused.iter().map(|slot| slot.get() as *const u64).collect();
+        addrs.sort_unstable();
+        freed.sort_unstable();
+        assert_eq!(addrs, freed);
+
+        slots.append(&mut reused);
+        unsafe { pool.free_many(slots) };
+        assert_eq!(pool.stats().freelist_len, 3000);
+    }
+
+    #[test]
+    fn ordered() {
+        strategy::<Ordered>();
+    }
+
+    #[test]
+    fn lifo() {
+        strategy::<Lifo>();
+
+        // the entry freed last is reused first
+        let pool: Pool<u64, Lifo> = Pool::with_strategy();
+        let a = pool.alloc(1);
+        let b = pool.alloc(2);
+        let addr = a.get() as *const u64;
+        unsafe {
+            pool.free(b);
+            pool.free(a);
+        }
+        let a = pool.alloc(3);
+        assert_eq!(a.get() as *const u64, addr);
+        unsafe { pool.free(a) };
+    }
+
+    #[test]
+    fn bitmap() {
+        strategy::<Bitmap>();
+
+        // the lowest free entry is reused first
+        let pool: Pool<u64, Bitmap> = Pool::with_strategy();
+        let mut slots: Vec<_> = (0..10).map(|i| pool.alloc(i)).collect();
+        let lowest = slots[2].get() as *const u64;
+        unsafe { pool.free_many(slots.drain(2..)) };
+        slots.push(pool.alloc(2));
+        assert_eq!(slots[2].get() as *const u64, lowest);
+        unsafe { pool.free_many(slots) };
+    }
+}
@@ -0,0 +1,134 @@
+use std::mem::size_of;
+
+use crate::*;
+
+/// Determines the sizes of the blocks a pool allocates. Set with
+/// `PoolApi::with_growth_policy()`.
+#[derive(Debug, Default, Copy, Clone)]
+pub enum GrowthPolicy {
+    /// Each block is twice as big as its predecessor. This is the default.
+    #[default]
+    Exponential,
+    /// Blocks double in size until they reach `max_block_bytes`, from then on all further
+    /// blocks have this size and the pool grows linearly.
+    CappedExponential {
+        /// The maximum size of a block in bytes.
+        max_block_bytes: usize,
+    },
+    /// All blocks hold the same number of entries. `min_entries` is ignored.
+    Fixed {
+        /// Number of entries per block.
+        entries: usize,
+    },
+    /// A user supplied function that gets the capacity of the last block (in entries) and
+    /// returns the capacity for the next block. The first block is sized by `min_entries`.
+    Custom(fn(usize) -> usize),
+}
+
+impl GrowthPolicy {
+    /// Calculates the capacity of a first block, takes `min_entries` as hint for the initial
+    /// blocksize calculation to contain at least this much entries.
+    pub(crate) fn first_capacity<T>(&self, min_entries: usize) -> Result<usize, AllocError> {
+        match *self {
+            GrowthPolicy::Fixed { entries } => Ok(std::cmp::max(entries, 1)),
+            _ => {
+                let min_entries = std::cmp::max(64, min_entries);
+
+                // generous rounding to next power of two
+                round_capacity::<T>(min_entries)
+            }
+        }
+    }
+
+    /// Calculates the capacity of a successor of a block holding `last` entries.
+    pub(crate) fn next_capacity<T>(&self, last: usize) -> Result<usize, AllocError> {
+        match *self {
+            GrowthPolicy::Exponential => {
+                round_capacity::<T>(last.checked_mul(2).ok_or(AllocError::LayoutOverflow)?)
+            }
+            GrowthPolicy::CappedExponential { max_block_bytes } => {
+                let max_entries = std::cmp::max(max_block_bytes / size_of::<Entry<T>>(), 1);
+                Ok(std::cmp::min(
+                    round_capacity::<T>(last.saturating_mul(2)).unwrap_or(max_entries),
+                    max_entries,
+                ))
+            }
+            GrowthPolicy::Fixed { entries } => Ok(std::cmp::max(entries, 1)),
+            GrowthPolicy::Custom(next) => Ok(std::cmp::max(next(last), 1)),
+        }
+    }
+}
+
+/// Rounds `entries` up so that the block size becomes a power of two.
+fn round_capacity<T>(entries: usize) -> Result<usize, AllocError> {
+    Ok(entries
+        .checked_mul(size_of::<Entry<T>>())
+        .and_then(usize::checked_next_power_of_two)
+        .ok_or(AllocError::LayoutOverflow)?
+        / size_of::<Entry<T>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::mem::size_of;
+
+    #[test]
+    fn exponential() {
+        let policy = GrowthPolicy::Exponential;
+        let first = policy.first_capacity::<u64>(0).unwrap();
+        assert_eq!(first, 64);
+        assert_eq!(policy.next_capacity::<u64>(first).unwrap(), 128);
+        assert_eq!(
+            policy.first_capacity::<u64>(usize::MAX / 2).unwrap_err(),
+            AllocError::LayoutOverflow
+        );
+    }
+
+    #[test]
+    fn capped_exponential() {
+        let policy = GrowthPolicy::CappedExponential {
+            max_block_bytes: 4096,
+        };
+        let max_entries = 4096 / size_of::<Entry<u64>>();
+        let mut capacity = policy.first_capacity::<u64>(0).unwrap();
+        for _ in 0..10 {
+            capacity = policy.next_capacity::<u64>(capacity).unwrap();
+        }
+        assert_eq!(capacity, max_entries);
+    }
+
+    #[test]
+    fn many_blocks() {
+        let pool: Pool<u64> = Pool::new();
+        pool.with_growth_policy(GrowthPolicy::Fixed { entries: 10 });
+
+        let mut slots = Vec::new();
+        for i in 0..1000 {
+            slots.push(pool.alloc(i));
+        }
+        let stats = pool.stats();
+        assert_eq!(stats.blocks_allocated, 100);
+        assert_eq!(stats.capacity, 1000);
+
+        unsafe {
+            slots.drain(..).for_each(|slot| pool.free(slot));
+        }
+    }
+
+    #[test]
+    fn custom() {
+        let pool: Pool<u64> = Pool::new();
+        pool.with_growth_policy(GrowthPolicy::Custom(|last| last + 64));
+
+        let mut slots = Vec::new();
+        for i in 0..64 + 128 + 192 {
+            slots.push(pool.alloc(i));
+        }
+        assert_eq!(pool.stats().blocks_allocated, 3);
+
+        unsafe {
+            slots.drain(..).for_each(|slot| pool.free(slot));
+        }
+    }
+}
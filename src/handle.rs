@@ -0,0 +1,191 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+use crate::*;
+
+/// A generational handle to an object in a pool. Unlike a `Slot` a handle is `Copy` and can
+/// be used safely: every entry carries a generation counter which is incremented whenever the
+/// entry is freed. Accessing an object through a handle whose object was already freed
+/// returns `None`, even when its entry got reallocated meanwhile.
+///
+/// Handles also record the process wide serial number of the block their entry lives in.
+/// When a block is released and its memory is reused by a new block, of the same or of
+/// another pool, handles to the old block are still rejected.
+///
+/// Handles are obtained from `pool.alloc_handle()` and are meant to be used with the pool
+/// they came from. The generation counter and the block serial are 32 bits wide, after 2^32
+/// reuses of the same entry or 2^32 block allocations a stale handle can become valid again.
+pub struct Handle<T> {
+    pub(crate) entry: NonNull<Entry<T>>,
+    pub(crate) block: u32,
+    pub(crate) generation: u32,
+    marker: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for Handle<T> {}
+unsafe impl<T: Sync> Sync for Handle<T> {}
+
+impl<T> Handle<T> {
+    pub(crate) fn new(entry: NonNull<Entry<T>>, block: u32, generation: u32) -> Self {
+        Self {
+            entry,
+            block,
+            generation,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.entry == other.entry
+            && self.block == other.block
+            && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.entry.hash(state);
+        self.block.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("Handle")
+            .field("entry", &self.entry)
+            .field("block", &self.block)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::alloc::Layout;
+    use std::ptr::NonNull;
+    use std::sync::Mutex;
+
+    #[test]
+    fn stale_handle() {
+        let pool: Pool<String> = Pool::new();
+        let handle = pool.alloc_handle(String::from("first"));
+        assert_eq!(pool.get_by_handle(handle, |s| s.clone()).unwrap(), "first");
+
+        pool.get_mut_by_handle(handle, |s| s.push_str(" changed"))
+            .unwrap();
+        assert_eq!(
+            pool.take_by_handle(handle).as_deref(),
+            Some("first changed")
+        );
+
+        // the entry gets reused, the old handle must not see the new object
+        let second = pool.alloc_handle(String::from("second"));
+        assert_eq!(second.entry, handle.entry);
+        assert!(!pool.contains_handle(handle));
+        assert_eq!(pool.get_by_handle(handle, |s| s.clone()), None);
+        assert!(!pool.free_by_handle(handle));
+
+        assert!(pool.contains_handle(second));
+        assert!(pool.free_by_handle(second));
+        assert!(!pool.free_by_handle(second));
+    }
+
+    /// Keeps the memory of deallocated blocks and hands it out again for the next block of
+    /// the same layout.
+    struct RecyclingAllocator(Mutex<Vec<(usize, Layout)>>);
+
+    unsafe impl BlockAllocator for RecyclingAllocator {
+        fn alloc(&self, layout: Layout) -> Option<NonNull<u8>> {
+            let mut cache = self.0.lock().unwrap();
+            match cache.iter().position(|&(_, cached)| cached == layout) {
+                Some(pos) => NonNull::new(cache.swap_remove(pos).0 as *mut u8),
+                None => GlobalBlockAllocator.alloc(layout),
+            }
+        }
+
+        unsafe fn dealloc(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.0.lock().unwrap().push((ptr.as_ptr() as usize, layout));
+        }
+    }
+
+    static RECYCLING: RecyclingAllocator = RecyclingAllocator(Mutex::new(Vec::new()));
+
+    #[test]
+    fn recycled_block() {
+        let pool: Pool<u64> = Pool::with_allocator(&RECYCLING);
+        let handle = pool.alloc_handle(1);
+        assert!(pool.free_by_handle(handle));
+        pool.shrink_to_fit();
+
+        // the new block reuses the memory of the released one, its generations start over
+        let second = pool.alloc_handle(2);
+        assert_eq!(second.entry, handle.entry);
+        assert_eq!(second.generation, handle.generation);
+        assert!(!pool.contains_handle(handle));
+        assert_eq!(pool.get_by_handle(handle, |v| *v), None);
+        assert!(!pool.free_by_handle(handle));
+        assert!(pool.free_by_handle(second));
+
+        pool.shrink_to_fit();
+        for (addr, layout) in RECYCLING.0.lock().unwrap().drain(..) {
+            unsafe { GlobalBlockAllocator.dealloc(NonNull::new(addr as *mut u8).unwrap(), layout) };
+        }
+    }
+
+    static CROSS_POOL: RecyclingAllocator = RecyclingAllocator(Mutex::new(Vec::new()));
+
+    #[test]
+    fn recycled_block_of_other_pool() {
+        let pool: Pool<u64> = Pool::with_allocator(&CROSS_POOL);
+        let handle = pool.alloc_handle(1);
+        assert!(pool.free_by_handle(handle));
+        drop(pool);
+
+        // the block of the new pool reuses the memory of the dropped pool
+        let other: Pool<u64> = Pool::with_allocator(&CROSS_POOL);
+        let mut slot = other.alloc(2).for_mutation();
+        assert_eq!(slot.get() as *const u64, handle.entry.as_ptr().cast());
+        assert!(!other.contains_handle(handle));
+        assert_eq!(other.get_mut_by_handle(handle, |v| *v), None);
+        assert_eq!(other.take_by_handle(handle), None);
+
+        // still rejected once the block tracks generations
+        let second = other.alloc_handle(3);
+        assert!(!other.contains_handle(handle));
+        assert_eq!(other.get_by_handle(handle, |v| *v), None);
+        *slot.get_mut() = 4;
+        assert_eq!(*slot.get(), 4);
+
+        assert!(other.free_by_handle(second));
+        unsafe { other.free(slot) };
+        drop(other);
+        for (addr, layout) in CROSS_POOL.0.lock().unwrap().drain(..) {
+            unsafe { GlobalBlockAllocator.dealloc(NonNull::new(addr as *mut u8).unwrap(), layout) };
+        }
+    }
+
+    #[test]
+    fn foreign_handle() {
+        let pool: Pool<u64> = Pool::new();
+        let other: Pool<u64> = Pool::new();
+        let handle = pool.alloc_handle(1234);
+        assert_eq!(other.get_by_handle(handle, |v| *v), None);
+        assert!(pool.free_by_handle(handle));
+    }
+}
@@ -0,0 +1,197 @@
+#![cfg(all(target_os = "linux", feature = "hugepages"))]
+use std::alloc::Layout;
+use std::ptr::NonNull;
+use std::sync::Mutex;
+
+use crate::*;
+
+/// Size of a huge page, blocks backed by huge pages are rounded up to and aligned at this.
+const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+/// How `HugePageBlockAllocator` requests huge pages from the kernel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HugePageMode {
+    /// Map blocks normally and advise the kernel to back them with transparent huge pages
+    /// (`MADV_HUGEPAGE`).
+    Transparent,
+    /// Map blocks from the preallocated huge page pool (`MAP_HUGETLB`). Falls back to
+    /// `Transparent` when no huge pages are available.
+    HugeTlb,
+}
+
+/// Allocates blocks of `threshold` bytes and more with `mmap()` and backs them by huge
+/// pages. This reduces TLB misses for large pools. Smaller blocks are allocated from the
+/// global rust allocator. When huge pages are not available the blocks are backed by normal
+/// pages, `PoolStats::huge_page_advised_blocks` tells for how many blocks huge pages were
+/// requested successfully and `pool.huge_page_advised_blocks()` for which ones.
+///
+/// ```
+/// use onsen::*;
+///
+/// static HUGE: HugePageBlockAllocator =
+///     HugePageBlockAllocator::new(4 * 1024 * 1024, HugePageMode::Transparent);
+///
+/// let pool: Pool<u64> = Pool::with_allocator(&HUGE);
+/// ```
+#[derive(Debug)]
+pub struct HugePageBlockAllocator {
+    threshold: usize,
+    mode: HugePageMode,
+    /// Addresses of the blocks that were mapped from the huge page pool or advised to use
+    /// transparent huge pages.
+    huge: Mutex<Vec<usize>>,
+}
+
+impl HugePageBlockAllocator {
+    /// Creates an allocator that backs blocks of at least `threshold` bytes by huge pages
+    /// requested by `mode`.
+    #[must_use]
+    pub const fn new(threshold: usize, mode: HugePageMode) -> Self {
+        Self {
+            threshold,
+            mode,
+            huge: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Maps `len` bytes aligned to `align`, both must be multiples of the huge page size.
+    fn map(len: usize, align: usize, flags: libc::c_int) -> Option<NonNull<u8>> {
+        let total = len.checked_add(align)?;
+        unsafe {
+            let addr = libc::mmap(
+                std::ptr::null_mut(),
+                total,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+                -1,
+                0,
+            );
+            if addr == libc::MAP_FAILED {
+                return None;
+            }
+
+            // trim the mapping to the requested alignment
+            let head = (addr as usize).next_multiple_of(align) - addr as usize;
+            let tail = total - head - len;
+            if head > 0 {
+                libc::munmap(addr, head);
+            }
+            if tail > 0 {
+                libc::munmap(addr.cast::<u8>().add(head + len).cast(), tail);
+            }
+            NonNull::new(addr.cast::<u8>().add(head))
+        }
+    }
+
+    fn set_huge(&self, ptr: NonNull<u8>) {
+        self.huge
+            .lock()
+            .expect("Failed to lock Mutex")
+            .push(ptr.as_ptr() as usize);
+    }
+}
+
+unsafe impl BlockAllocator for HugePageBlockAllocator {
+    fn alloc(&self, layout: Layout) -> Option<NonNull<u8>> {
+        if layout.size() < self.threshold {
+            return GlobalBlockAllocator.alloc(layout);
+        }
+
+        let len = layout.size().checked_next_multiple_of(HUGE_PAGE_SIZE)?;
+        let align = std::cmp::max(layout.align(), HUGE_PAGE_SIZE);
+
+        if self.mode == HugePageMode::HugeTlb {
+            if let Some(ptr) = Self::map(len, align, libc::MAP_HUGETLB) {
+                self.set_huge(ptr);
+                return Some(ptr);
+            }
+        }
+
+        let ptr = Self::map(len, align, 0)?;
+        if unsafe { libc::madvise(ptr.as_ptr().cast(), len, libc::MADV_HUGEPAGE) } == 0 {
+            self.set_huge(ptr);
+        }
+        Some(ptr)
+    }
+
+    unsafe fn dealloc(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() < self.threshold {
+            return GlobalBlockAllocator.dealloc(ptr, layout);
+        }
+
+        self.huge
+            .lock()
+            .expect("Failed to lock Mutex")
+            .retain(|&addr| addr != ptr.as_ptr() as usize);
+        libc::munmap(
+            ptr.as_ptr().cast(),
+            layout.size().next_multiple_of(HUGE_PAGE_SIZE),
+        );
+    }
+
+    fn is_huge_page_advised(&self, ptr: NonNull<u8>, _layout: Layout) -> bool {
+        self.huge
+            .lock()
+            .expect("Failed to lock Mutex")
+            .contains(&(ptr.as_ptr() as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    static TRANSPARENT: HugePageBlockAllocator =
+        HugePageBlockAllocator::new(1024 * 1024, HugePageMode::Transparent);
+
+    static HUGETLB: HugePageBlockAllocator =
+        HugePageBlockAllocator::new(1024 * 1024, HugePageMode::HugeTlb);
+
+    fn alloc_huge(allocator: &'static HugePageBlockAllocator) {
+        let pool: Pool<u64> = Pool::with_allocator(allocator);
+        pool.with_growth_policy(GrowthPolicy::Fixed { entries: 1000 });
+        let small: Vec<_> = (0..1000).map(|i| pool.alloc(i)).collect();
+        assert_eq!(pool.stats().huge_page_advised_blocks, 0);
+        assert_eq!(pool.huge_page_advised_blocks(), vec![false]);
+        unsafe { pool.free_many(small) };
+        pool.shrink_to_fit();
+
+        // a block of 4MB is above the threshold
+        pool.with_growth_policy(GrowthPolicy::Fixed { entries: 1 << 19 });
+        let slots: Vec<_> = (0..1 << 19).map(|i| pool.alloc(i)).collect();
+        assert!(slots
+            .iter()
+            .enumerate()
+            .all(|(i, slot)| *slot.get() == i as u64));
+        // whether huge pages can be requested depends on the host, the statistics only have
+        // to agree with the allocator
+        let stats = pool.stats();
+        assert_eq!(stats.blocks_allocated, 1);
+        assert!(stats.huge_page_advised_blocks <= 1);
+        assert_eq!(
+            stats.huge_page_advised_blocks,
+            allocator.huge.lock().unwrap().len()
+        );
+        assert_eq!(
+            pool.huge_page_advised_blocks(),
+            vec![stats.huge_page_advised_blocks == 1]
+        );
+
+        unsafe { pool.free_many(slots) };
+        pool.shrink_to_fit();
+        assert_eq!(pool.stats().huge_page_advised_blocks, 0);
+        assert!(pool.huge_page_advised_blocks().is_empty());
+        assert!(allocator.huge.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn transparent() {
+        alloc_huge(&TRANSPARENT);
+    }
+
+    #[test]
+    fn hugetlb() {
+        // falls back to transparent huge pages when none are reserved
+        alloc_huge(&HUGETLB);
+    }
+}
@@ -9,9 +9,6 @@
 #![warn(missing_docs)]
 #![warn(rustdoc::missing_crate_level_docs)]
 
-/// Maximum number of blocks in a Pool
-pub(crate) const NUM_BLOCKS: usize = 44;
-
 #[cfg(feature = "tbox")]
 #[doc(hidden)]
 pub use assoc_static::*;
@@ -19,6 +16,16 @@ pub use assoc_static::*;
 mod block;
 use block::*;
 
+mod allocator;
+pub use allocator::*;
+
+mod hugepages;
+#[cfg(all(target_os = "linux", feature = "hugepages"))]
+pub use hugepages::*;
+
+mod growth;
+pub use growth::*;
+
 mod pool;
 pub use pool::*;
 
@@ -35,9 +42,21 @@ pub use stpool::*;
 mod slot;
 pub use slot::*;
 
+mod handle;
+pub use handle::*;
+
+mod movable;
+pub use movable::*;
+
 mod entry;
 pub use entry::*;
 
+mod freelist;
+pub use freelist::*;
+
+mod observer;
+pub use observer::*;
+
 mod boxed;
 pub use boxed::*;
 
@@ -63,6 +82,30 @@ pub use tstrongcounted::*;
 #[derive(Debug, Copy, Clone)]
 pub struct PoolOwnershipError;
 
+/// The error returned by the fallible allocation functions when a pool can not provide an
+/// entry.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AllocError {
+    /// The size of a new block can not be represented.
+    LayoutOverflow,
+    /// The memory allocator failed to allocate a new block.
+    OutOfMemory,
+    /// The configured entry or byte limit of the pool is reached.
+    CapacityExceeded,
+}
+
+impl std::fmt::Display for AllocError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            AllocError::LayoutOverflow => "block layout overflow",
+            AllocError::OutOfMemory => "out of memory",
+            AllocError::CapacityExceeded => "pool capacity exceeded",
+        })
+    }
+}
+
+impl std::error::Error for AllocError {}
+
 #[cfg(test)]
 mod tests {
     use crate::*;
@@ -0,0 +1,162 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+/// A handle to an object that the pool may relocate. Movables do not point to their object
+/// directly but to an entry of an indirection table in the pool. This allows
+/// `pool.compact()` to move their objects into lower blocks and release blocks that became
+/// sparse after heavy churn.
+///
+/// Movables are obtained from `pool.alloc_movable()` or by converting an initialized or
+/// mutable slot with `pool.make_movable()`. Objects are accessed by closures while the pool
+/// is locked, thus no reference to them can be alive while they are moved. Since a pinned
+/// object must never move there is no way to turn a `Pinnable` slot into a movable. A movable
+/// can be turned back into a fixed slot by `pool.movable_into_slot()`.
+///
+/// Like slots, movables must be used only with the pool they came from. Other pools panic
+/// or access one of their own objects.
+pub struct Movable<T> {
+    pub(crate) index: u32,
+    marker: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for Movable<T> {}
+unsafe impl<T: Sync> Sync for Movable<T> {}
+
+impl<T> Movable<T> {
+    pub(crate) fn new(index: u32) -> Self {
+        Self {
+            index,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Movable<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("Movable")
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn access() {
+        let pool: Pool<String> = Pool::new();
+        let mut movable = pool.alloc_movable(String::from("first"));
+        assert_eq!(pool.get_by_movable(&movable, |s| s.clone()), "first");
+        pool.get_mut_by_movable(&mut movable, |s| s.push_str(" changed"));
+        assert_eq!(pool.take_movable(movable), "first changed");
+
+        let slot = pool.alloc(String::from("second")).for_mutation();
+        let movable = unsafe { pool.make_movable(slot) };
+        let slot = unsafe { pool.movable_into_slot(movable) }.for_pinning();
+        assert_eq!(slot.get(), "second");
+        unsafe { pool.free(slot) };
+        assert_eq!(pool.stats().in_use, 0);
+    }
+
+    #[test]
+    fn compact() {
+        let pool: Pool<u64> = Pool::new();
+        pool.with_growth_policy(GrowthPolicy::Fixed { entries: 100 });
+        let mut movables: Vec<_> = (0..400).map(|i| pool.alloc_movable(i)).collect();
+        assert_eq!(pool.stats().blocks_allocated, 4);
+
+        // keep every tenth object, they fit into the first block
+        let mut kept = Vec::new();
+        for (i, movable) in movables.drain(..).enumerate() {
+            if i % 10 == 0 {
+                kept.push((i as u64, movable));
+            } else {
+                pool.free_movable(movable);
+            }
+        }
+        assert_eq!(pool.compact(), 3 * 100 * std::mem::size_of::<Entry<u64>>());
+
+        let stats = pool.stats();
+        assert_eq!(stats.blocks_allocated, 1);
+        assert_eq!(stats.in_use, 40);
+        for (value, movable) in kept {
+            assert_eq!(pool.take_movable(movable), value);
+        }
+        // blocks that were emptied by freeing are not released by compacting
+        assert_eq!(pool.compact(), 0);
+        assert_eq!(pool.stats().blocks_allocated, 1);
+    }
+
+    #[test]
+    fn freed_by_retain() {
+        let pool: Pool<u64> = Pool::new();
+        pool.with_growth_policy(GrowthPolicy::Fixed { entries: 100 });
+        let first: Vec<_> = (0..100).map(|i| pool.alloc_movable(i)).collect();
+        let second: Vec<_> = (100..200).map(|i| pool.alloc_movable(i)).collect();
+        for movable in first.into_iter().skip(1) {
+            pool.free_movable(movable);
+        }
+
+        // free the objects of the second block behind the back of their movables and reuse
+        // their entries for pinned objects
+        unsafe { pool.retain(|value| *value < 100) };
+        std::mem::forget(second);
+        let mut pinned: Vec<_> = (0..50).map(|i| pool.alloc(i).for_pinning()).collect();
+        let addr = pinned[49].get_pin().get_mut() as *mut u64;
+
+        // the entries of the pinned objects are not movable anymore
+        assert_eq!(pool.compact(), 0);
+        assert_eq!(pool.stats().blocks_allocated, 2);
+        assert_eq!(pinned[49].get_pin().get_mut() as *mut u64, addr);
+        assert_eq!(*pinned[49].get(), 49);
+        unsafe {
+            pool.clear();
+            std::mem::forget(pinned);
+        }
+    }
+
+    #[test]
+    fn reserved_blocks_kept() {
+        let pool: Pool<u64> = Pool::new();
+        pool.with_growth_policy(GrowthPolicy::Fixed { entries: 100 });
+        let movables: Vec<_> = (0..200).map(|i| pool.alloc_movable(i)).collect();
+        pool.reserve(100);
+        assert_eq!(pool.stats().blocks_allocated, 3);
+
+        let mut kept = Vec::new();
+        for (i, movable) in movables.into_iter().enumerate() {
+            if i >= 150 {
+                kept.push((i as u64, movable));
+            } else {
+                pool.free_movable(movable);
+            }
+        }
+
+        // only the block emptied by moving is released, the first and the reserved one stay
+        assert_eq!(pool.compact(), 100 * std::mem::size_of::<Entry<u64>>());
+        assert_eq!(pool.stats().blocks_allocated, 2);
+        for (value, movable) in kept {
+            assert_eq!(pool.take_movable(movable), value);
+        }
+    }
+
+    #[test]
+    fn pinned_never_move() {
+        let pool: Pool<u64> = Pool::new();
+        pool.with_growth_policy(GrowthPolicy::Fixed { entries: 100 });
+        let movables: Vec<_> = (0..200).map(|i| pool.alloc_movable(i)).collect();
+        let mut pinned = pool.alloc(1234).for_pinning();
+        let addr = pinned.get_pin().get_mut() as *mut u64;
+        for movable in movables {
+            pool.free_movable(movable);
+        }
+
+        // the block holding the pinned object stays, it is not moved into a free lower block
+        assert_eq!(pool.compact(), 0);
+        assert_eq!(pool.stats().blocks_allocated, 3);
+        assert_eq!(pinned.get_pin().get_mut() as *mut u64, addr);
+        assert_eq!(*pinned.get(), 1234);
+        unsafe { pool.free(pinned) };
+    }
+}
@@ -0,0 +1,134 @@
+/// Callbacks for instrumenting a pool. An observer is attached to a pool at construction
+/// with `with_observer()` and becomes part of the pools type. All callbacks default to doing
+/// nothing, an observer implements only the ones it is interested in. Pools constructed with
+/// `new()` use `NoObserver` which compiles to nothing.
+///
+/// The callbacks are invoked while the pool is locked, they must not access the pool.
+///
+/// ```
+/// use onsen::*;
+/// use std::cell::Cell;
+///
+/// #[derive(Default)]
+/// struct Counter(Cell<usize>);
+///
+/// impl PoolObserver for Counter {
+///     fn on_alloc(&self) {
+///         self.0.set(self.0.get() + 1);
+///     }
+/// }
+///
+/// let counter = Counter::default();
+/// let pool: Pool<u64, Ordered, &Counter> = Pool::with_observer(&counter);
+/// let slot = pool.alloc(1234);
+/// unsafe { pool.free(slot) };
+/// assert_eq!(counter.0.get(), 1);
+/// ```
+pub trait PoolObserver {
+    /// Called after an entry was allocated.
+    #[inline(always)]
+    fn on_alloc(&self) {}
+
+    /// Called when an entry is given back to the pool.
+    #[inline(always)]
+    fn on_free(&self) {}
+
+    /// Called after a new block for `capacity` entries taking `bytes` of memory was
+    /// allocated.
+    #[inline(always)]
+    fn on_block_alloc(&self, _capacity: usize, _bytes: usize) {}
+
+    /// Called when a block for `capacity` entries taking `bytes` of memory is given back to
+    /// the allocator.
+    #[inline(always)]
+    fn on_block_free(&self, _capacity: usize, _bytes: usize) {}
+}
+
+/// The observer of pools that are not observed, all callbacks are no-ops.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct NoObserver;
+
+impl PoolObserver for NoObserver {}
+
+impl<O: PoolObserver + ?Sized> PoolObserver for &O {
+    #[inline(always)]
+    fn on_alloc(&self) {
+        O::on_alloc(self);
+    }
+
+    #[inline(always)]
+    fn on_free(&self) {
+        O::on_free(self);
+    }
+
+    #[inline(always)]
+    fn on_block_alloc(&self, capacity: usize, bytes: usize) {
+        O::on_block_alloc(self, capacity, bytes);
+    }
+
+    #[inline(always)]
+    fn on_block_free(&self, capacity: usize, bytes: usize) {
+        O::on_block_free(self, capacity, bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::cell::Cell;
+
+    #[derive(Default)]
+    struct Events {
+        allocs: Cell<usize>,
+        frees: Cell<usize>,
+        blocks: Cell<usize>,
+        bytes: Cell<usize>,
+    }
+
+    impl PoolObserver for Events {
+        fn on_alloc(&self) {
+            self.allocs.set(self.allocs.get() + 1);
+        }
+
+        fn on_free(&self) {
+            self.frees.set(self.frees.get() + 1);
+        }
+
+        fn on_block_alloc(&self, _capacity: usize, bytes: usize) {
+            self.blocks.set(self.blocks.get() + 1);
+            self.bytes.set(self.bytes.get() + bytes);
+        }
+
+        fn on_block_free(&self, _capacity: usize, bytes: usize) {
+            self.blocks.set(self.blocks.get() - 1);
+            self.bytes.set(self.bytes.get() - bytes);
+        }
+    }
+
+    #[test]
+    fn events() {
+        let events = Events::default();
+        {
+            let pool: Pool<u64, Ordered, &Events> = Pool::with_observer(&events);
+            pool.with_growth_policy(GrowthPolicy::Fixed { entries: 100 });
+            let slots = pool.alloc_many(0..250);
+            assert_eq!(events.allocs.get(), 250);
+            assert_eq!(events.blocks.get(), 3);
+            assert_eq!(events.bytes.get(), pool.stats().bytes_reserved);
+
+            unsafe { pool.free_many(slots) };
+            assert_eq!(events.frees.get(), 250);
+            let slot = pool.alloc(1);
+            unsafe { pool.free(slot) };
+            assert_eq!(events.frees.get(), 251);
+
+            pool.shrink_to_fit();
+            assert_eq!(events.blocks.get(), 0);
+            pool.reserve(1);
+            assert_eq!(events.blocks.get(), 1);
+        }
+        // dropping the pool frees its blocks
+        assert_eq!(events.blocks.get(), 0);
+        assert_eq!(events.bytes.get(), 0);
+    }
+}
@@ -1,38 +1,86 @@
 use std::cell::RefCell;
 use std::fmt;
-use std::mem::ManuallyDrop;
+use std::marker::PhantomData;
+use std::mem::{size_of, ManuallyDrop};
 use std::ptr::NonNull;
 
 use crate::*;
 
-/// A single threaded, interior mutable memory Pool holding objects of type T.
-pub struct Pool<T: Sized>(RefCell<PoolInner<T>>);
+/// A single threaded, interior mutable memory Pool holding objects of type T. The
+/// `FreelistStrategy` `L` defines how free entries are reused, the `PoolObserver` `O` gets
+/// notified about allocations and block growth.
+pub struct Pool<T: Sized, L: FreelistStrategy = Ordered, O: PoolObserver = NoObserver>(
+    RefCell<PoolInner<T, L, O>>,
+);
 
 impl<T> Pool<T> {
     /// Creates a new Pool for objects of type T.
     #[inline]
     #[must_use]
     pub const fn new() -> Self {
+        Self::with_strategy()
+    }
+
+    /// Creates a new Pool for objects of type T which allocates its blocks from `allocator`.
+    #[inline]
+    #[must_use]
+    pub const fn with_allocator(allocator: &'static dyn BlockAllocator) -> Self {
+        Self(RefCell::new(PoolInner::with_allocator(allocator)))
+    }
+}
+
+impl<T, L: FreelistStrategy> Pool<T, L> {
+    /// Creates a new Pool for objects of type T which reuses free entries according to
+    /// the `FreelistStrategy` `L`.
+    #[inline]
+    #[must_use]
+    pub const fn with_strategy() -> Self {
         Self(RefCell::new(PoolInner::new()))
     }
 }
 
-impl<T> PoolApi<T> for Pool<T> {}
+impl<T, L: FreelistStrategy, O: PoolObserver> Pool<T, L, O> {
+    /// Creates a new Pool for objects of type T which reports its events to `observer`.
+    #[inline]
+    #[must_use]
+    pub const fn with_observer(observer: O) -> Self {
+        Self(RefCell::new(PoolInner::with_observer(
+            &GlobalBlockAllocator,
+            observer,
+        )))
+    }
+
+    /// Creates a new Pool for objects of type T which allocates its blocks from `allocator`
+    /// and reports its events to `observer`.
+    #[inline]
+    #[must_use]
+    pub const fn with_allocator_and_observer(
+        allocator: &'static dyn BlockAllocator,
+        observer: O,
+    ) -> Self {
+        Self(RefCell::new(PoolInner::with_observer(allocator, observer)))
+    }
+}
+
+impl<T, L: FreelistStrategy, O: PoolObserver> PoolApi<T> for Pool<T, L, O> {}
+
+impl<T, L: FreelistStrategy, O: PoolObserver> PoolLock<T> for &Pool<T, L, O> {
+    type Freelist = L;
+    type Observer = O;
 
-impl<T> PoolLock<T> for &Pool<T> {
     #[inline]
-    fn with_lock<R, F: FnOnce(&mut PoolInner<T>) -> R>(self, f: F) -> R {
+    fn with_lock<R, F: FnOnce(&mut PoolInner<T, L, O>) -> R>(self, f: F) -> R {
         f(&mut self.0.borrow_mut())
     }
 }
 
-impl<T> Default for Pool<T> {
+impl<T, L: FreelistStrategy, O: PoolObserver + Default> Default for Pool<T, L, O> {
     fn default() -> Self {
-        Self::new()
+        Self::with_observer(O::default())
     }
 }
 
-impl<T> fmt::Debug for Pool<T> {
+impl<T, L: FreelistStrategy, O: PoolObserver> fmt::Debug for Pool<T, L, O> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         f.debug_tuple("Pool").field(&self.0).finish()
     }
@@ -41,7 +89,31 @@ impl<T> fmt::Debug for Pool<T> {
 /// Interior mutability of a pool.
 #[doc(hidden)]
 pub trait PoolLock<T> {
-    fn with_lock<R, F: FnOnce(&mut PoolInner<T>) -> R>(self, f: F) -> R;
+    /// The `FreelistStrategy` of the pool.
+    type Freelist: FreelistStrategy;
+    /// The `PoolObserver` of the pool.
+    type Observer: PoolObserver;
+
+    fn with_lock<R, F: FnOnce(&mut PoolInner<T, Self::Freelist, Self::Observer>) -> R>(
+        self,
+        f: F,
+    ) -> R;
+
+    /// Called after an entry was given back to the pool.
+    #[inline(always)]
+    fn notify_free(self)
+    where
+        Self: Sized,
+    {
+    }
+
+    /// Called after many entries were given back or blocks were released at once.
+    #[inline(always)]
+    fn notify_free_all(self)
+    where
+        Self: Sized,
+    {
+    }
 }
 
 /// The API for a Pool. This trait takes care for the locking the interior mutable pools and
@@ -55,6 +127,10 @@ pub trait PoolLock<T> {
 /// This API is low-level and frequently unsafe is intended to be used to build
 /// safe high level abstractions.
 ///
+/// Zero sized types take no memory in a pool, all their slots share a single static address.
+/// They are only counted, thus they are not visited by iteration, `clear()` or when dropping
+/// a pool with `with_drop_live()` enabled, and they can not be used with handles or indices.
+///
 /// This trait must be in scope to be used.
 pub trait PoolApi<T>
 where
@@ -67,7 +143,7 @@ where
     /// locality.  Since blocks are allocated with exponentially growing size this should be
     /// still small enough, approx 1/4 of the average number of entries to be expected. The
     /// implementation will generously round this up to the next power of two. When not set it
-    /// defaults to 64 entries.
+    /// defaults to 64 entries. Ignored by `GrowthPolicy::Fixed`.
     ///
     /// # Panics
     ///
@@ -76,280 +152,2268 @@ where
         self.with_lock(|pool| pool.min_entries = min_entries);
     }
 
-    /// Destroys a Pool while leaking its allocated blocks.  The fast way out when one knows
-    /// that allocations still exist and will never be returned to to the Pool. Either because
-    /// the program exits or because the allocations are meant to stay.
-    #[inline]
-    fn leak(self) {
-        std::mem::forget(self);
+    /// Configures how the sizes of new blocks are calculated. Affects only blocks allocated
+    /// after this call, thus it should be called before the first allocation is made. The
+    /// default is `GrowthPolicy::Exponential`.
+    fn with_growth_policy(&self, growth_policy: GrowthPolicy) {
+        self.with_lock(|pool| pool.growth_policy = growth_policy);
     }
 
-    /// Allocates a new entry, either from the freelist or by extending the pool.
-    /// Returns Entry pointer tagged as UNINITIALIZED.
-    fn alloc_entry(&self) -> NonNull<Entry<T>> {
-        self.with_lock(|pool| pool.alloc_entry())
+    /// Limits the number of entries that can be in use at the same time. When the limit is
+    /// reached the fallible allocation functions return `AllocError::CapacityExceeded` and
+    /// the infallible ones panic. Blocks are never allocated larger than necessary to hold
+    /// this number of entries. Defaults to unlimited.
+    fn with_max_entries(&self, max_entries: usize) {
+        self.with_lock(|pool| pool.max_entries = max_entries);
     }
 
-    /// Allocates a new slot from the pool, initializes it with the supplied object and
-    /// returns a slot handle. Freeing the object should be done manually with `pool.free()`,
-    /// `pool::forget()` or `pool.take()`. The user must take care that the slot/references
-    /// obtained from it are not used after free as this may panic or return another object.
-    #[must_use = "Slot is required for freeing memory, dropping it will leak"]
-    #[inline]
-    fn alloc(&self, t: T) -> Slot<T, Initialized> {
-        let mut entry = self.alloc_entry();
-        unsafe {
-            *entry.as_mut() = Entry {
-                data: ManuallyDrop::new(t),
-            };
-        }
-        Slot::new(entry)
+    /// Limits the total size of all blocks of this pool to `max_bytes`. The last block will
+    /// be allocated smaller than normal to fit into this budget. When no more blocks can be
+    /// allocated the fallible allocation functions return `AllocError::CapacityExceeded` and
+    /// the infallible ones panic. Defaults to unlimited.
+    ///
+    /// Only the memory of the blocks themselves is limited, as reported by
+    /// `PoolStats::bytes_reserved`. The per block bookkeeping is allocated separately and not
+    /// counted: the bitmaps of initialized, free, released and freed entries, the generation
+    /// counters and the back indices of movables. This adds up to at most 8 bytes and 4 bits
+    /// per entry, see the README for when each of them is allocated.
+    fn with_max_bytes(&self, max_bytes: usize) {
+        self.with_lock(|pool| pool.max_bytes = max_bytes);
     }
 
-    /// Non consuming variant of `pool.free()`, allows freeing slots that are part of other
-    /// structures while keeping Slot non-Copy. The slot must not be used after this.
-    /// See `slot.free()` for details.
-    #[allow(clippy::missing_safety_doc)]
-    #[allow(clippy::missing_panics_doc)]
-    unsafe fn free_by_ref<S: DropPolicy>(&self, slot: &mut Slot<T, S>) {
-        self.with_lock(|pool| {
-            S::manually_drop(&mut slot.0.as_mut().data);
-            pool.free_entry(slot.0.as_ptr());
-        });
+    /// Configures what happens when the pool gets dropped while entries are still in use.
+    /// Defaults to `LeakPolicy::Panic` in debug builds and `LeakPolicy::Leak` in release
+    /// builds.
+    fn with_leak_policy(&self, leak_policy: LeakPolicy) {
+        self.with_lock(|pool| pool.leak_policy = leak_policy);
     }
 
-    /// Non consuming variant of `pool.take()`, allows taking slots that are part of other
-    /// structures while keeping Slot non-Copy.  The slot must not be used after this.  See
-    /// `slot.take()` for details.
-    #[allow(clippy::missing_safety_doc)]
-    #[allow(clippy::missing_panics_doc)]
-    unsafe fn take_by_ref<S: CanTakeValue>(&self, slot: &mut Slot<T, S>) -> T {
-        self.with_lock(|pool| {
-            let ret = ManuallyDrop::take(&mut slot.0.as_mut().data);
-            pool.free_entry(slot.0.as_ptr());
-            ret
-        })
+    /// Turns the pool into an arena: when it gets dropped, the destructors of all live
+    /// initialized objects are run and the memory is released. Entries that are still
+    /// uninitialized are released without running any destructor. The `LeakPolicy` does not
+    /// apply in this mode.
+    ///
+    /// # Safety
+    ///
+    /// No `Slot` or reference obtained from this pool must be used after the pool is dropped.
+    unsafe fn with_drop_live(&self, drop_live: bool) {
+        self.with_lock(|pool| pool.drop_live = drop_live);
     }
 
-    /// Allocates a new slot from the pool, keeps the content uninitialized returns a Slot
-    /// handle to it. Freeing the object should be done manually with `pool.free()` or
-    /// `pool.forget()`. The user must take care that the provided handle or references
-    /// obtained from is are used after free as this may panic or return another object.
-    #[must_use = "Slot is required for freeing memory, dropping it will leak"]
-    #[inline]
-    fn alloc_uninit(&self) -> Slot<T, Uninitialized> {
-        Slot::new(self.alloc_entry())
+    /// Runs the destructors of all live initialized objects and resets the pool to an empty
+    /// state. The blocks are kept allocated and will be reused by subsequent allocations.
+    /// Entries on the freelist and entries that are still uninitialized are not dropped.
+    ///
+    /// Destructors are called while the pool is not locked, they may free other entries of
+    /// this pool.
+    ///
+    /// # Safety
+    ///
+    /// All `Slots` and `Movables` obtained from this pool become invalid, neither they nor any
+    /// references obtained from them must be used afterwards.
+    unsafe fn clear(&self) {
+        let mut cursor = (0, 0);
+        while let Some(entry) = self.with_lock(|pool| pool.next_initialized(&mut cursor)) {
+            ManuallyDrop::drop(&mut *Entry::data_ptr(entry));
+            self.with_lock(|pool| pool.free_entry(entry.as_ptr()));
+        }
+        let zsts = self.with_lock(|pool| std::mem::take(&mut pool.initialized_zsts));
+        for _ in 0..zsts {
+            ManuallyDrop::drop(&mut *Entry::data_ptr(Entry::<T>::zst()));
+        }
+        self.with_lock(|pool| pool.reset());
+        self.notify_free_all();
     }
 
-    /// Frees `slot` by calling its destructor when it contains an initialized object,
-    /// uninitialized objects become forgotten as with `Pool::forget()`. Puts the given slot
-    /// back into the freelist.
+    /// Returns the compact index of `slot`, see `slot.into_index()`.
+    ///
+    /// # Panics
+    ///
+    ///  * The slot is not from this pool.
+    ///  * The index does not fit into 32 bits.
+    #[must_use]
+    fn index_of<S: Policy>(&self, slot: &Slot<T, S>) -> u32 {
+        u32::try_from(self.with_lock(|pool| pool.index_of(slot.0.as_ptr())))
+            .expect("Index does not fit into 32 bits")
+    }
+
+    /// Converts an index obtained by `slot.into_index()` back into a Slot. In debug builds
+    /// it is validated that the index refers to an entry that was handed out by this pool
+    /// and, when poisoning is enabled, that this entry is not freed.
     ///
     /// # Safety
     ///
-    /// Slots must not be freed while references pointing to it.
+    /// The index must be obtained from a slot of this pool which is still allocated. The
+    /// policy `S` must be the same as the one of the original slot.
     ///
     /// # Panics
     ///
-    ///  * The slot is already free
-    ///  * The slot is invalid, not from this pool (debug only).
-    #[inline]
-    unsafe fn free<S: DropPolicy>(&self, mut slot: Slot<T, S>) {
-        self.free_by_ref(&mut slot);
+    ///  * The index does not belong to this pool (debug only).
+    ///  * The entry at index is freed (debug only, with poisoning).
+    #[must_use]
+    unsafe fn slot_from_index<S: Policy>(&self, index: u32) -> Slot<T, S> {
+        Slot::new(self.with_lock(|pool| pool.entry_at(index as usize)))
     }
 
-    /// Puts the given slot back into the freelist. Will not call the the destructor.
+    /// Allocates a new entry initialized with `t` and returns a generational `Handle` to it.
     ///
-    /// # Safety
+    /// # Panics
     ///
-    /// Slots must not be forgotten while references pointing to it.
+    ///  * When no entry can be allocated, see `try_alloc_handle()`.
+    ///  * `T` is a zero sized type.
+    #[must_use = "Handle is required for freeing memory, dropping it will leak"]
+    fn alloc_handle(&self, t: T) -> Handle<T> {
+        self.try_alloc_handle(t).expect("Allocation failure")
+    }
+
+    /// Fallible variant of `alloc_handle()`. Returns an `AllocError` instead of panicking
+    /// when no entry can be allocated.
     ///
     /// # Panics
     ///
-    ///  * The slot is already free
-    ///  * The slot is invalid, not from this pool (debug only).
-    #[inline]
-    unsafe fn forget<S: Policy>(&self, mut slot: Slot<T, S>) {
-        self.forget_by_ref(&mut slot);
+    /// `T` is a zero sized type, these have no generation counter.
+    fn try_alloc_handle(&self, t: T) -> Result<Handle<T>, AllocError> {
+        assert!(
+            !Entry::<T>::IS_ZST,
+            "Handles are not supported for zero sized types"
+        );
+        self.with_lock(|pool| {
+            let (block, entry) = pool.alloc_entry_in_block()?;
+            if let Err(error) = unsafe { pool.block_mut(block) }.track_generations() {
+                unsafe { pool.free_entry(entry.as_ptr()) };
+                return Err(error);
+            }
+            let entry = unsafe { pool.init_entry(block, entry, t) };
+            Ok(pool.handle_of(block, entry))
+        })
     }
 
-    /// Non consuming variant of `pool.forget()`, allows forgetting slots that are part of
-    /// other structures while keeping Slot non-Copy.  The slot must not be used after this.
-    /// See `slot.forget()` for details.
-    #[allow(clippy::missing_safety_doc)]
-    #[allow(clippy::missing_panics_doc)]
-    unsafe fn forget_by_ref<S: Policy>(&self, slot: &mut Slot<T, S>) {
+    /// Returns true when `handle` refers to a live object of this pool.
+    fn contains_handle(&self, handle: Handle<T>) -> bool {
+        self.with_lock(|pool| pool.handle_entry(handle).is_some())
+    }
+
+    /// Calls `f` with a reference to the object `handle` refers to. Returns `None` when the
+    /// handle is stale or does not belong to this pool. The pool stays locked while `f` runs.
+    fn get_by_handle<R, F: FnOnce(&T) -> R>(&self, handle: Handle<T>, f: F) -> Option<R> {
         self.with_lock(|pool| {
-            pool.free_entry(slot.0.as_ptr());
+            pool.handle_entry(handle)
+                .map(|entry| f(unsafe { &entry.as_ref().data }))
+        })
+    }
+
+    /// Calls `f` with a mutable reference to the object `handle` refers to. Returns `None`
+    /// when the handle is stale or does not belong to this pool. The pool stays locked while
+    /// `f` runs.
+    fn get_mut_by_handle<R, F: FnOnce(&mut T) -> R>(&self, handle: Handle<T>, f: F) -> Option<R> {
+        self.with_lock(|pool| {
+            pool.handle_entry(handle)
+                .map(|entry| f(unsafe { &mut *Entry::data_ptr(entry) }))
+        })
+    }
+
+    /// Frees the object `handle` refers to and returns it. Returns `None` when the handle is
+    /// stale or does not belong to this pool.
+    fn take_by_handle(&self, handle: Handle<T>) -> Option<T> {
+        let ret = self.with_lock(|pool| {
+            pool.handle_entry(handle).map(|entry| unsafe {
+                let ret = ManuallyDrop::take(&mut *Entry::data_ptr(entry));
+                pool.free_entry(entry.as_ptr());
+                ret
+            })
         });
+        self.notify_free();
+        ret
     }
 
-    /// Takes an object out of the Pool and returns it. The slot at `slot` is put back to the
-    /// freelist. This operation violates the Pin guarantees, thus in presence of pinned
-    /// operation it must not be used.
+    /// Frees the object `handle` refers to by calling its destructor. Returns false when the
+    /// handle is stale or does not belong to this pool.
+    fn free_by_handle(&self, handle: Handle<T>) -> bool {
+        let freed = self.with_lock(|pool| {
+            pool.handle_entry(handle)
+                .map(|entry| unsafe {
+                    ManuallyDrop::drop(&mut *Entry::data_ptr(entry));
+                    pool.free_entry(entry.as_ptr());
+                })
+                .is_some()
+        });
+        self.notify_free();
+        freed
+    }
+
+    /// Allocates a new entry initialized with `t` and returns a `Movable` to it. The object
+    /// may be relocated by `compact()`.
+    ///
+    /// # Panics
+    ///
+    /// When no entry can be allocated, see `try_alloc_movable()`.
+    #[must_use = "Movable is required for freeing memory, dropping it will leak"]
+    fn alloc_movable(&self, t: T) -> Movable<T> {
+        self.try_alloc_movable(t).expect("Allocation failure")
+    }
+
+    /// Fallible variant of `alloc_movable()`. Returns an `AllocError` instead of panicking
+    /// when no entry can be allocated.
+    ///
+    /// # Panics
+    ///
+    /// More than 2^32 movables are in use.
+    fn try_alloc_movable(&self, t: T) -> Result<Movable<T>, AllocError> {
+        self.with_lock(|pool| {
+            let (block, entry) = pool.alloc_entry_in_block()?;
+            let entry = unsafe { pool.init_entry(block, entry, t) };
+            Ok(pool.add_movable(entry))
+        })
+    }
+
+    /// Turns an initialized or mutable slot into a `Movable`, its object may be relocated by
+    /// `compact()` from now on.
     ///
     /// # Safety
     ///
-    /// Slots must not be taken while references pointing to it.
+    /// The slot must belong to this pool. No copies of the slot and no references to its
+    /// object must exist.
     ///
     /// # Panics
     ///
-    ///  * The object at slot is not initialized
-    ///  * The object at slot was ever pinned
-    ///  * The slot is already free
-    ///  * The slot is invalid, not from this pool (debug only).
-    #[inline]
-    unsafe fn take<S: CanTakeValue>(&self, mut slot: Slot<T, S>) -> T {
-        self.take_by_ref(&mut slot)
+    ///  * The slot is not from this pool.
+    ///  * More than 2^32 movables are in use.
+    unsafe fn make_movable<S: CanMove>(&self, slot: Slot<T, S>) -> Movable<T> {
+        self.with_lock(|pool| {
+            pool.mark_initialized(slot.0.as_ptr());
+            pool.add_movable(slot.0)
+        })
     }
-}
 
-/// Actual Pool implementations bits which need protected access
-#[doc(hidden)]
-pub struct PoolInner<T: Sized> {
-    blocks: [Option<Block<T>>; NUM_BLOCKS],
-    blocks_allocated: usize,
-    min_entries: usize,
-    in_use: usize,
-    freelist: Option<NonNull<Entry<T>>>,
-}
+    /// Turns a `Movable` back into a slot. Its object stays at its current address forever
+    /// and can be pinned.
+    ///
+    /// # Safety
+    ///
+    /// The returned slot must not outlive the pool, see `Slot`.
+    ///
+    /// # Panics
+    ///
+    /// The movable is not from this pool.
+    #[must_use = "Slot is required for freeing memory, dropping it will leak"]
+    unsafe fn movable_into_slot(&self, movable: Movable<T>) -> Slot<T, Initialized> {
+        Slot::new(self.with_lock(|pool| pool.remove_movable(movable)))
+    }
 
-unsafe impl<T: Sized + Send> Send for PoolInner<T> {}
+    /// Calls `f` with a reference to the object of `movable`. The pool stays locked while `f`
+    /// runs, thus the object can not be moved meanwhile.
+    ///
+    /// # Panics
+    ///
+    /// The movable is not from this pool.
+    fn get_by_movable<R, F: FnOnce(&T) -> R>(&self, movable: &Movable<T>, f: F) -> R {
+        self.with_lock(|pool| f(unsafe { &pool.movable_entry(movable).as_ref().data }))
+    }
 
-impl<T> PoolInner<T> {
-    pub(crate) const fn new() -> Self {
-        Self {
-            // blocks: [(); NUM_BLOCKS].map(|_| None),  // doesn't work in constfn :/
-
-            // TODO:  https://github.com/rust-lang/rust/issues/76001
-            // which reduces it down to just `[const { None }; SIZE]`
-            blocks: [
-                None, None, None, None, None, None, None, None, None, None, None, None, None, None,
-                None, None, None, None, None, None, None, None, None, None, None, None, None, None,
-                None, None, None, None, None, None, None, None, None, None, None, None, None, None,
-                None, None,
-            ],
-            blocks_allocated: 0,
-            min_entries: 64,
-            in_use: 0,
-            freelist: None,
-        }
+    /// Calls `f` with a mutable reference to the object of `movable`. The pool stays locked
+    /// while `f` runs, thus the object can not be moved meanwhile.
+    ///
+    /// # Panics
+    ///
+    /// The movable is not from this pool.
+    fn get_mut_by_movable<R, F: FnOnce(&mut T) -> R>(&self, movable: &mut Movable<T>, f: F) -> R {
+        self.with_lock(|pool| f(unsafe { &mut *Entry::data_ptr(pool.movable_entry(movable)) }))
     }
 
-    /// Allocate an entry, creating a new Block when required.
-    fn alloc_entry(&mut self) -> NonNull<Entry<T>> {
-        let entry = if let Some(mut entry) = self.freelist {
-            // from freelist
-            self.freelist = unsafe { entry.as_mut().remove_free_node() };
-            entry
-        } else {
-            // from block
-            if self.blocks_allocated == 0 {
-                // allocate initial block
-                self.blocks[0] = Some(Block::new_first(self.min_entries));
-                self.blocks_allocated += 1;
-            } else if unsafe {
-                self.blocks
-                    .get_unchecked(self.blocks_allocated - 1)
-                    .as_ref()
-                    .unwrap_unchecked()
-                    .is_full()
-            } {
-                // allocate new block
-                self.blocks[self.blocks_allocated] = Some(Block::new_next(unsafe {
-                    self.blocks
-                        .get_unchecked(self.blocks_allocated - 1)
-                        .as_ref()
-                        .unwrap_unchecked()
-                }));
-                self.blocks_allocated += 1;
-            }
+    /// Frees the object of `movable` and returns it.
+    ///
+    /// # Panics
+    ///
+    /// The movable is not from this pool.
+    fn take_movable(&self, movable: Movable<T>) -> T {
+        let ret = self.with_lock(|pool| unsafe {
+            let entry = pool.remove_movable(movable);
+            let ret = ManuallyDrop::take(&mut *Entry::data_ptr(entry));
+            pool.release_object::<Initialized>();
+            pool.free_entry(entry.as_ptr());
+            ret
+        });
+        self.notify_free();
+        ret
+    }
 
-            unsafe {
-                self.blocks
-                    .get_unchecked_mut(self.blocks_allocated - 1)
-                    .as_mut()
-                    .unwrap_unchecked()
-                    .extend()
-            }
-        };
+    /// Frees the object of `movable` by calling its destructor.
+    ///
+    /// # Panics
+    ///
+    /// The movable is not from this pool.
+    fn free_movable(&self, movable: Movable<T>) {
+        self.with_lock(|pool| unsafe {
+            let entry = pool.remove_movable(movable);
+            ManuallyDrop::drop(&mut *Entry::data_ptr(entry));
+            pool.release_object::<Initialized>();
+            pool.free_entry(entry.as_ptr());
+        });
+        self.notify_free();
+    }
 
-        self.in_use += 1;
-        entry
+    /// Calls `f` with an iterator over all live initialized objects in this pool. The pool
+    /// stays locked while `f` runs, thus `f` must not allocate from or free to this pool.
+    /// Objects that were initialized through `slot.assume_init()` instead of
+    /// `pool.assume_init()` are not visited.
+    ///
+    /// # Safety
+    ///
+    /// No mutable references to objects in this pool must exist while `f` runs.
+    unsafe fn iter_live<R, F: FnOnce(LiveIter<'_, T>) -> R>(&self, f: F) -> R {
+        self.with_lock(|pool| {
+            f(LiveIter {
+                blocks: &pool.blocks,
+                cursor: (0, 0),
+            })
+        })
     }
 
-    /// Put entry back into the freelist.
+    /// Calls `f` on every live initialized object in this pool, see `iter_live()`.
     ///
     /// # Safety
     ///
-    ///  * The object must be already destructed (if possible)
-    ///  * No references to the entry must exist
+    /// No mutable references to objects in this pool must exist while `f` runs.
+    unsafe fn for_each_live<F: FnMut(&T)>(&self, f: F) {
+        self.iter_live(|iter| iter.for_each(f));
+    }
+
+    /// Calls `f` with a mutable reference to every live initialized object in this pool, see
+    /// `iter_live()`.
     ///
-    /// This is internal, only called from Slot
-    unsafe fn free_entry(&mut self, entry: *mut Entry<T>) {
-        if let Some(freelist_last) = self.freelist {
-            self.blocks[0..self.blocks_allocated]
-                .iter()
-                .rev()
-                .map(|block| block.as_ref().unwrap_unchecked())
-                .find(|block| block.contains_entry(entry))
-                .map(|_| {
-                    let list_node = freelist_last.as_ptr();
-                    Entry::insert_free_node(list_node, entry);
-                })
-                .expect("Entry not in Pool");
-        } else {
-            Entry::init_free_node(entry);
-        }
-        self.in_use -= 1;
-        self.freelist = Some(NonNull::new_unchecked(entry));
+    /// # Safety
+    ///
+    /// No references to objects in this pool must exist while `f` runs.
+    unsafe fn for_each_live_mut<F: FnMut(&mut T)>(&self, mut f: F) {
+        self.with_lock(|pool| {
+            let mut cursor = (0, 0);
+            while let Some(entry) = pool.next_initialized(&mut cursor) {
+                f(&mut *Entry::data_ptr(entry));
+            }
+        });
     }
 
-    fn freelist_len(&self) -> usize {
-        let mut len = 0;
-        if self.freelist.is_some() {
-            len += 1;
-            let start = self.freelist.unwrap().as_ptr();
-            let mut entry = start;
-            unsafe {
-                while (*entry).freelist_node.next != start {
-                    len += 1;
-                    entry = (*entry).freelist_node.next;
+    /// Calls `f` with a mutable reference to every live initialized object in this pool and
+    /// frees the objects for which `f` returns false, running their destructors. See
+    /// `iter_live()`.
+    ///
+    /// # Safety
+    ///
+    ///  * No references to objects in this pool must exist while `f` runs.
+    ///  * The `Slots` or `Movables` of the freed objects must not be used afterwards.
+    unsafe fn retain<F: FnMut(&mut T) -> bool>(&self, mut f: F) {
+        self.with_lock(|pool| {
+            let mut cursor = (0, 0);
+            while let Some(entry) = pool.next_initialized(&mut cursor) {
+                if !f(&mut *Entry::data_ptr(entry)) {
+                    ManuallyDrop::drop(&mut *Entry::data_ptr(entry));
+                    pool.free_entry(entry.as_ptr());
                 }
             }
-        }
+        });
+        self.notify_free_all();
+    }
 
-        len
+    /// Returns a snapshot of the pools statistics.
+    #[must_use]
+    fn stats(&self) -> PoolStats {
+        self.with_lock(|pool| pool.stats())
     }
-}
 
-impl<T> fmt::Debug for PoolInner<T> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        f.debug_struct("PoolInner")
-            .field("blocks", &self.blocks)
-            .field("blocks_allocated", &self.blocks_allocated)
-            .field("min_entries", &self.min_entries)
-            .field("in_use", &self.in_use)
-            .field("freelist.len()", &self.freelist_len())
-            .finish()
+    /// Returns for each block, in the order they were allocated, whether huge pages were
+    /// requested successfully for it. `PoolStats::huge_page_advised_blocks` is the number of
+    /// `true` entries.
+    #[must_use]
+    fn huge_page_advised_blocks(&self) -> Vec<bool> {
+        self.with_lock(|pool| {
+            pool.blocks
+                .iter()
+                .map(Block::is_huge_page_advised)
+                .collect()
+        })
     }
-}
 
-#[cfg(test)]
-mod pool_tests {
-    use crate::*;
+    /// Reserves capacity for at least `additional` more entries. The next `additional`
+    /// allocations are then guaranteed not to allocate a new block. Blocks are allocated
+    /// with the same exponentially growing sizes as they would be on demand.
+    ///
+    /// # Panics
+    ///
+    /// When the required blocks can not be allocated.
+    fn reserve(&self, additional: usize) {
+        self.with_lock(|pool| pool.reserve(additional))
+            .expect("Allocation failure");
+    }
 
-    #[test]
-    fn smoke() {
-        let _pool: Pool<String> = Pool::new();
+    /// Gives trailing blocks that have no entries in use back to the allocator. Free entries
+    /// in these blocks are removed from the freelist. Blocks are released from the end only,
+    /// a block that still has entries in use keeps all blocks before it alive.  This can be
+    /// used to return memory after a burst of allocations was freed again.
+    fn shrink_to_fit(&self) {
+        self.with_lock(|pool| pool.shrink_to_fit());
+        self.notify_free_all();
     }
 
-    #[test]
-    fn leak() {
-        let pool: Pool<u64> = Pool::new();
-        let _ = pool.alloc(1234);
+    /// Moves the objects of `Movables` into free entries of lower blocks and gives the blocks
+    /// that became empty by this back to the allocator. Only blocks whose entries in use all
+    /// belong to movables can be emptied. Objects referenced by slots or handles, pinned
+    /// objects in particular, are never moved. Blocks that were empty before, like the ones
+    /// allocated by `reserve()`, are kept. Returns the number of bytes released.
+    ///
+    /// The moved objects take free entries of the lower blocks while the free entries of the
+    /// released blocks go away, thus capacity reserved by `reserve()` before may be lost.
+    /// Call `reserve()` again after compacting when its guarantee is needed.
+    fn compact(&self) -> usize {
+        let released = self.with_lock(|pool| pool.compact());
+        self.notify_free_all();
+        released
+    }
+
+    /// Gives the memory of pages inside the blocks that hold only free entries back to the
+    /// operating system with `madvise(MADV_DONTNEED)`. The address range stays reserved, the
+    /// pages are faulted in again when their entries get reused. Unlike `shrink_to_fit()`
+    /// this works for free entries in the middle of blocks. Returns the number of bytes
+    /// released.
+    #[cfg(target_os = "linux")]
+    fn release_free_pages(&self) -> usize {
+        self.with_lock(|pool| pool.release_free_pages())
+    }
+
+    /// Destroys a Pool while leaking its allocated blocks.  The fast way out when one knows
+    /// that allocations still exist and will never be returned to to the Pool. Either because
+    /// the program exits or because the allocations are meant to stay.
+    #[inline]
+    fn leak(self) {
+        std::mem::forget(self);
+    }
+
+    /// Allocates a new entry, either from the freelist or by extending the pool.
+    /// Returns Entry pointer tagged as UNINITIALIZED.
+    ///
+    /// # Panics
+    ///
+    /// When no entry can be allocated, see `try_alloc_entry()`.
+    fn alloc_entry(&self) -> NonNull<Entry<T>> {
+        self.try_alloc_entry().expect("Allocation failure")
+    }
+
+    /// Fallible variant of `alloc_entry()`. Returns an `AllocError` instead of panicking when
+    /// no entry can be allocated.
+    fn try_alloc_entry(&self) -> Result<NonNull<Entry<T>>, AllocError> {
+        self.with_lock(|pool| pool.alloc_entry())
+    }
+
+    /// Allocates a new slot from the pool, initializes it with the supplied object and
+    /// returns a slot handle. Freeing the object should be done manually with `pool.free()`,
+    /// `pool::forget()` or `pool.take()`. The user must take care that the slot/references
+    /// obtained from it are not used after free as this may panic or return another object.
+    #[must_use = "Slot is required for freeing memory, dropping it will leak"]
+    #[inline]
+    fn alloc(&self, t: T) -> Slot<T, Initialized> {
+        self.try_alloc(t).expect("Allocation failure")
+    }
+
+    /// Fallible variant of `alloc()`. Returns an `AllocError` instead of panicking when no
+    /// entry can be allocated. The supplied object is dropped in this case.
+    #[inline]
+    fn try_alloc(&self, t: T) -> Result<Slot<T, Initialized>, AllocError> {
+        let entry = self.with_lock(|pool| {
+            let (block, entry) = pool.alloc_entry_in_block()?;
+            Ok(unsafe { pool.init_entry(block, entry, t) })
+        })?;
+        Ok(Slot::new(entry))
+    }
+
+    /// Allocates a new slot from the pool and initializes it with the object returned by
+    /// `f`. The object is written directly into the pools memory, this allows the optimizer to
+    /// construct large objects in place instead of moving them through the stack. Rust does
+    /// not guarantee this, unoptimized builds usually copy the object through the stack. `f`
+    /// is called while the pool is not locked, it may allocate from this pool.
+    ///
+    /// # Panics
+    ///
+    /// When no entry can be allocated, see `try_alloc_with()`. When `f` panics the entry is
+    /// leaked.
+    #[must_use = "Slot is required for freeing memory, dropping it will leak"]
+    #[inline]
+    fn alloc_with<F: FnOnce() -> T>(&self, f: F) -> Slot<T, Initialized> {
+        self.try_alloc_with(f).expect("Allocation failure")
+    }
+
+    /// Fallible variant of `alloc_with()`. Returns an `AllocError` instead of panicking when
+    /// no entry can be allocated, `f` is not called in this case.
+    #[inline]
+    fn try_alloc_with<F: FnOnce() -> T>(&self, f: F) -> Result<Slot<T, Initialized>, AllocError> {
+        let mut slot = self.try_alloc_uninit()?;
+        slot.get_uninit().write(f());
+        Ok(unsafe { self.assume_init(slot) })
+    }
+
+    /// Allocates a slot for each object of `iter`. The objects are collected before the pool
+    /// is locked, then the pool is locked only once for all allocations.
+    ///
+    /// # Panics
+    ///
+    /// When not all entries can be allocated, see `try_alloc_many()`.
+    #[must_use = "Slots are required for freeing memory, dropping them will leak"]
+    fn alloc_many<I: IntoIterator<Item = T>>(&self, iter: I) -> Vec<Slot<T, Initialized>> {
+        self.try_alloc_many(iter).expect("Allocation failure")
+    }
+
+    /// Fallible variant of `alloc_many()`. Either all objects are allocated or none, when
+    /// not all entries can be allocated the ones allocated so far are given back, the objects
+    /// are dropped and an `AllocError` is returned.
+    fn try_alloc_many<I: IntoIterator<Item = T>>(
+        &self,
+        iter: I,
+    ) -> Result<Vec<Slot<T, Initialized>>, AllocError> {
+        // neither the iterator nor the destructors of the objects run under the lock
+        let mut objects: Vec<T> = iter.into_iter().collect();
+        self.with_lock(|pool| {
+            let mut entries = Vec::new();
+            pool.alloc_entries_in_blocks(objects.len(), &mut entries)?;
+            Ok(entries
+                .into_iter()
+                .zip(objects.drain(..))
+                .map(|((block, entry), t)| Slot::new(unsafe { pool.init_entry(block, entry, t) }))
+                .collect())
+        })
+    }
+
+    /// Allocates `n` uninitialized slots. The pool is locked only once for all allocations.
+    ///
+    /// # Panics
+    ///
+    /// When not all entries can be allocated, see `try_alloc_many_uninit()`.
+    #[must_use = "Slots are required for freeing memory, dropping them will leak"]
+    fn alloc_many_uninit(&self, n: usize) -> Vec<Slot<T, Uninitialized>> {
+        self.try_alloc_many_uninit(n).expect("Allocation failure")
+    }
+
+    /// Fallible variant of `alloc_many_uninit()`. Either all `n` entries are allocated or
+    /// none, an `AllocError` is returned otherwise.
+    fn try_alloc_many_uninit(&self, n: usize) -> Result<Vec<Slot<T, Uninitialized>>, AllocError> {
+        self.with_lock(|pool| {
+            let mut entries = Vec::new();
+            pool.alloc_entries_in_blocks(n, &mut entries)?;
+            Ok(entries
+                .into_iter()
+                .map(|(_, entry)| Slot::new(entry))
+                .collect())
+        })
+    }
+
+    /// Frees all slots of `slots` as with `pool.free()`. The pool is locked only once. The
+    /// entries are sorted and given back in address order, which keeps the freelist ordered.
+    /// Because of that the destructors are not called in iteration order.
+    ///
+    /// # Safety
+    ///
+    /// Slots must not be freed while references pointing to it.
+    ///
+    /// # Panics
+    ///
+    ///  * A slot is already free (debug builds or with the `poison` feature).
+    ///  * A slot is invalid, not from this pool.
+    unsafe fn free_many<S: DropPolicy, I: IntoIterator<Item = Slot<T, S>>>(&self, slots: I) {
+        let mut entries: Vec<NonNull<Entry<T>>> = slots.into_iter().map(|slot| slot.0).collect();
+        entries.sort_unstable();
+        self.with_lock(|pool| {
+            for &entry in &entries {
+                S::manually_drop(&mut *Entry::data_ptr(entry));
+                pool.release_object::<S>();
+            }
+            pool.free_sorted_entries(&entries);
+        });
+        self.notify_free_all();
+    }
+
+    /// Non consuming variant of `pool.free()`, allows freeing slots that are part of other
+    /// structures while keeping Slot non-Copy. The slot must not be used after this.
+    /// See `slot.free()` for details.
+    #[allow(clippy::missing_safety_doc)]
+    #[allow(clippy::missing_panics_doc)]
+    unsafe fn free_by_ref<S: DropPolicy>(&self, slot: &mut Slot<T, S>) {
+        self.with_lock(|pool| {
+            S::manually_drop(&mut *Entry::data_ptr(slot.0));
+            pool.release_object::<S>();
+            pool.free_entry(slot.0.as_ptr());
+        });
+        self.notify_free();
+    }
+
+    /// Non consuming variant of `pool.take()`, allows taking slots that are part of other
+    /// structures while keeping Slot non-Copy.  The slot must not be used after this.  See
+    /// `slot.take()` for details.
+    #[allow(clippy::missing_safety_doc)]
+    #[allow(clippy::missing_panics_doc)]
+    unsafe fn take_by_ref<S: CanTakeValue>(&self, slot: &mut Slot<T, S>) -> T {
+        let ret = self.with_lock(|pool| {
+            let ret = ManuallyDrop::take(&mut *Entry::data_ptr(slot.0));
+            pool.release_object::<S>();
+            pool.free_entry(slot.0.as_ptr());
+            ret
+        });
+        self.notify_free();
+        ret
+    }
+
+    /// Allocates a new slot from the pool, keeps the content uninitialized returns a Slot
+    /// handle to it. Freeing the object should be done manually with `pool.free()` or
+    /// `pool.forget()`. The user must take care that the provided handle or references
+    /// obtained from is are used after free as this may panic or return another object.
+    #[must_use = "Slot is required for freeing memory, dropping it will leak"]
+    #[inline]
+    fn alloc_uninit(&self) -> Slot<T, Uninitialized> {
+        Slot::new(self.alloc_entry())
+    }
+
+    /// Fallible variant of `alloc_uninit()`. Returns an `AllocError` instead of panicking
+    /// when no entry can be allocated.
+    #[inline]
+    fn try_alloc_uninit(&self) -> Result<Slot<T, Uninitialized>, AllocError> {
+        Ok(Slot::new(self.try_alloc_entry()?))
+    }
+
+    /// Initializes `slot` with `value` and returns an initialized Slot. This is the safe
+    /// variant of writing through `slot.get_uninit()` and calling `pool.assume_init()`.
+    ///
+    /// # Panics
+    ///
+    /// The slot is not from this pool.
+    #[inline]
+    #[must_use = "Slot is required for freeing memory, dropping it will leak"]
+    fn init(&self, mut slot: Slot<T, Uninitialized>, value: T) -> Slot<T, Initialized> {
+        // validates that the slot belongs to this pool before anything is written
+        self.with_lock(|pool| pool.mark_initialized(slot.0.as_ptr()));
+        slot.get_uninit().write(value);
+        unsafe { slot.assume_init() }
+    }
+
+    /// Variant of `slot.assume_init()` that also lets the pool know that the slot is now
+    /// initialized. Objects initialized by `slot.assume_init()` are not dropped by
+    /// `pool.clear()` or when dropping a pool with `with_drop_live()` enabled.
+    ///
+    /// # Safety
+    ///
+    /// The slot must be initialized and belong to this pool.
+    ///
+    /// # Panics
+    ///
+    /// The slot is not from this pool.
+    #[inline]
+    unsafe fn assume_init(&self, slot: Slot<T, Uninitialized>) -> Slot<T, Initialized> {
+        self.with_lock(|pool| pool.mark_initialized(slot.0.as_ptr()));
+        slot.assume_init()
+    }
+
+    /// Frees `slot` by calling its destructor when it contains an initialized object,
+    /// uninitialized objects become forgotten as with `Pool::forget()`. Puts the given slot
+    /// back into the freelist.
+    ///
+    /// # Safety
+    ///
+    /// Slots must not be freed while references pointing to it.
+    ///
+    /// # Panics
+    ///
+    ///  * The slot is already free (debug builds or with the `poison` feature).
+    ///  * The slot is invalid, not from this pool.
+    #[inline]
+    unsafe fn free<S: DropPolicy>(&self, mut slot: Slot<T, S>) {
+        self.free_by_ref(&mut slot);
+    }
+
+    /// Puts the given slot back into the freelist. Will not call the the destructor.
+    ///
+    /// # Safety
+    ///
+    /// Slots must not be forgotten while references pointing to it.
+    ///
+    /// # Panics
+    ///
+    ///  * The slot is already free (debug builds or with the `poison` feature).
+    ///  * The slot is invalid, not from this pool.
+    #[inline]
+    unsafe fn forget<S: Policy>(&self, mut slot: Slot<T, S>) {
+        self.forget_by_ref(&mut slot);
+    }
+
+    /// Non consuming variant of `pool.forget()`, allows forgetting slots that are part of
+    /// other structures while keeping Slot non-Copy.  The slot must not be used after this.
+    /// See `slot.forget()` for details.
+    #[allow(clippy::missing_safety_doc)]
+    #[allow(clippy::missing_panics_doc)]
+    unsafe fn forget_by_ref<S: Policy>(&self, slot: &mut Slot<T, S>) {
+        self.with_lock(|pool| {
+            pool.release_object::<S>();
+            pool.free_entry(slot.0.as_ptr());
+        });
+        self.notify_free();
+    }
+
+    /// Takes an object out of the Pool and returns it. The slot at `slot` is put back to the
+    /// freelist. This operation violates the Pin guarantees, thus in presence of pinned
+    /// operation it must not be used.
+    ///
+    /// # Safety
+    ///
+    /// Slots must not be taken while references pointing to it.
+    ///
+    /// # Panics
+    ///
+    ///  * The object at slot is not initialized
+    ///  * The object at slot was ever pinned
+    ///  * The slot is already free (debug builds or with the `poison` feature).
+    ///  * The slot is invalid, not from this pool.
+    #[inline]
+    unsafe fn take<S: CanTakeValue>(&self, mut slot: Slot<T, S>) -> T {
+        self.take_by_ref(&mut slot)
+    }
+}
+
+/// Block index returned for allocations of zero sized types, these do not live in a block.
+const ZST_BLOCK: usize = usize::MAX;
+
+/// Actual Pool implementations bits which need protected access
+#[doc(hidden)]
+pub struct PoolInner<T: Sized, L: FreelistStrategy = Ordered, O: PoolObserver = NoObserver> {
+    blocks: Vec<Block<T>>,
+    /// Indices into `blocks` sorted by the address of their memory, used to find the block
+    /// an entry belongs to.
+    blocks_by_address: Vec<usize>,
+    /// The block new entries are taken from when the freelist is empty.
+    current_block: usize,
+    min_entries: usize,
+    growth_policy: GrowthPolicy,
+    max_entries: usize,
+    max_bytes: usize,
+    leak_policy: LeakPolicy,
+    drop_live: bool,
+    in_use: usize,
+    /// Number of initialized zero sized objects. These share a single entry and have no
+    /// initialized bitmap, `clear()` and `with_drop_live()` drop this many of them.
+    initialized_zsts: usize,
+    high_water: usize,
+    /// A block with free entries, new entries are taken from its freelist first. `None` when
+    /// the freelists of all blocks are empty.
+    free_block: Option<usize>,
+    /// Indices of the blocks that got free entries, the next `free_block` is taken from here.
+    /// Blocks whose free entries were used up meanwhile are only removed when they come up.
+    free_blocks: Vec<usize>,
+    /// The indirection table of the movables, `None` for unused table entries.
+    movables: Vec<Option<NonNull<Entry<T>>>>,
+    /// Indices of the unused entries in `movables`.
+    free_movables: Vec<u32>,
+    allocator: &'static dyn BlockAllocator,
+    observer: O,
+    strategy: PhantomData<L>,
+}
+
+unsafe impl<T: Sized + Send, L: FreelistStrategy, O: PoolObserver + Send> Send
+    for PoolInner<T, L, O>
+{
+}
+
+impl<T, L: FreelistStrategy> PoolInner<T, L> {
+    pub(crate) const fn new() -> Self {
+        Self::with_allocator(&GlobalBlockAllocator)
+    }
+
+    pub(crate) const fn with_allocator(allocator: &'static dyn BlockAllocator) -> Self {
+        Self::with_observer(allocator, NoObserver)
+    }
+}
+
+impl<T, L: FreelistStrategy, O: PoolObserver> PoolInner<T, L, O> {
+    pub(crate) const fn with_observer(allocator: &'static dyn BlockAllocator, observer: O) -> Self {
+        Self {
+            blocks: Vec::new(),
+            blocks_by_address: Vec::new(),
+            current_block: 0,
+            min_entries: 64,
+            growth_policy: GrowthPolicy::Exponential,
+            max_entries: usize::MAX,
+            max_bytes: usize::MAX,
+            leak_policy: LeakPolicy::new(),
+            drop_live: false,
+            in_use: 0,
+            initialized_zsts: 0,
+            high_water: 0,
+            free_block: None,
+            free_blocks: Vec::new(),
+            movables: Vec::new(),
+            free_movables: Vec::new(),
+            allocator,
+            observer,
+            strategy: PhantomData,
+        }
+    }
+
+    /// Allocate an entry, creating a new Block when required.
+    pub(crate) fn alloc_entry(&mut self) -> Result<NonNull<Entry<T>>, AllocError> {
+        self.alloc_entry_in_block().map(|(_, entry)| entry)
+    }
+
+    /// Allocate an entry, returns the index of the block it belongs to as well.
+    pub(crate) fn alloc_entry_in_block(
+        &mut self,
+    ) -> Result<(usize, NonNull<Entry<T>>), AllocError> {
+        if self.in_use >= self.max_entries {
+            return Err(AllocError::CapacityExceeded);
+        }
+
+        let entry = if Entry::<T>::IS_ZST {
+            // zero sized types need no memory
+            (ZST_BLOCK, Entry::zst())
+        } else if let Some(block) = self.free_block {
+            // from freelist
+            let entry = unsafe { self.block_mut(block).pop_free::<L>().unwrap_unchecked() };
+            if !unsafe { self.block(block) }.has_free() {
+                self.free_block = self.find_free_block();
+            }
+            (block, entry)
+        } else {
+            // from block
+            if self.blocks.is_empty() {
+                // allocate initial block
+                self.grow()?;
+            } else if unsafe { self.block(self.current_block) }.is_full() {
+                if self.current_block + 1 == self.blocks.len() {
+                    // allocate new block
+                    self.grow()?;
+                }
+                // advance to the next (possibly reserved) block
+                self.current_block += 1;
+            }
+
+            (self.current_block, unsafe {
+                self.block_mut(self.current_block).extend()
+            })
+        };
+
+        self.in_use += 1;
+        self.high_water = std::cmp::max(self.high_water, self.in_use);
+        self.observer.on_alloc();
+        Ok(entry)
+    }
+
+    /// Allocates `n` entries and appends them together with the index of the block each
+    /// belongs to to `entries`, which must be empty. When not all entries can be allocated
+    /// the ones allocated so far are freed again.
+    fn alloc_entries_in_blocks(
+        &mut self,
+        n: usize,
+        entries: &mut Vec<(usize, NonNull<Entry<T>>)>,
+    ) -> Result<(), AllocError> {
+        entries
+            .try_reserve_exact(n)
+            .map_err(|_| AllocError::OutOfMemory)?;
+        for _ in 0..n {
+            match self.alloc_entry_in_block() {
+                Ok(entry) => entries.push(entry),
+                Err(error) => {
+                    for (_, entry) in entries.drain(..).rev() {
+                        unsafe { self.free_entry(entry.as_ptr()) };
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Put entry back into the freelist.
+    ///
+    /// # Safety
+    ///
+    ///  * The object must be already destructed (if possible)
+    ///  * No references to the entry must exist
+    ///
+    /// This is internal, only called from Slot
+    unsafe fn free_entry(&mut self, entry: *mut Entry<T>) {
+        if Entry::<T>::IS_ZST {
+            debug_assert_eq!(entry, Entry::zst().as_ptr(), "Entry not in Pool");
+            self.in_use -= 1;
+            self.observer.on_free();
+            return;
+        }
+
+        let (block, index) = self.release_entry(entry);
+        self.block_mut(block).push_free::<L>(index);
+        self.set_free_block(block);
+    }
+
+    /// Puts entries sorted by address back into the freelist. The first entry of each block
+    /// is inserted like with `free_entry()`, the others are linked in as one ascending run
+    /// after it.
+    ///
+    /// # Safety
+    ///
+    /// Same as `free_entry()` for each entry.
+    unsafe fn free_sorted_entries(&mut self, entries: &[NonNull<Entry<T>>]) {
+        if Entry::<T>::IS_ZST {
+            entries
+                .iter()
+                .for_each(|entry| self.free_entry(entry.as_ptr()));
+            return;
+        }
+
+        let mut last_block = None;
+        for entry in entries {
+            let (block, index) = self.release_entry(entry.as_ptr());
+            if last_block == Some(block) {
+                self.block_mut(block).push_free_ascending::<L>(index);
+            } else {
+                self.block_mut(block).push_free::<L>(index);
+                self.set_free_block(block);
+                last_block = Some(block);
+            }
+        }
+    }
+
+    /// Accounts for an entry that is about to be put back into the freelist. Returns the
+    /// index of its block and its index within the block.
+    unsafe fn release_entry(&mut self, entry: *mut Entry<T>) -> (usize, usize) {
+        let block_index = self.block_of(entry).expect("Entry not in Pool");
+        let block = self.block_mut(block_index);
+        let index = block.index_of(entry);
+        if POISONING {
+            assert!(!block.is_freed(index), "Slot already freed");
+        }
+        block.dec_live();
+        block.clear_initialized(index);
+        block.inc_generation(index);
+        if POISONING {
+            block.set_freed(index);
+            Entry::poison(entry);
+        }
+        // a movable of a freed object must not keep the entry as movable when it gets reused
+        if let Some(movable) = block.clear_movable(index) {
+            self.movables[movable as usize] = None;
+            self.free_movables.push(movable);
+        }
+        self.in_use -= 1;
+        self.observer.on_free();
+        (block_index, index)
+    }
+
+    /// Makes `block`, which just got a free entry, the block new entries are taken from.
+    fn set_free_block(&mut self, block: usize) {
+        self.free_block = Some(block);
+        let block_ref = unsafe { self.block_mut(block) };
+        if !block_ref.listed_free {
+            block_ref.listed_free = true;
+            self.free_blocks.push(block);
+        }
+    }
+
+    /// Returns the index of some block with free entries. Drops the blocks without free
+    /// entries from the top of the `free_blocks` stack.
+    fn find_free_block(&mut self) -> Option<usize> {
+        while let Some(&block) = self.free_blocks.last() {
+            let block_ref = unsafe { self.block_mut(block) };
+            if block_ref.has_free() {
+                return Some(block);
+            }
+            block_ref.listed_free = false;
+            self.free_blocks.pop();
+        }
+        None
+    }
+
+    /// Rebuilds the `free_blocks` stack after blocks were released, the block with the lowest
+    /// index ends up on top.
+    fn rebuild_free_blocks(&mut self) {
+        self.free_blocks.clear();
+        for (index, block) in self.blocks.iter_mut().enumerate().rev() {
+            block.listed_free = block.has_free();
+            if block.listed_free {
+                self.free_blocks.push(index);
+            }
+        }
+        self.free_block = self.free_blocks.last().copied();
+    }
+
+    /// Moves `t` into a freshly allocated entry and marks it as initialized.
+    ///
+    /// # Safety
+    ///
+    /// `entry` must be allocated from block `block` and uninitialized.
+    pub(crate) unsafe fn init_entry(
+        &mut self,
+        block: usize,
+        mut entry: NonNull<Entry<T>>,
+        t: T,
+    ) -> NonNull<Entry<T>> {
+        if Entry::<T>::IS_ZST {
+            // the shared zero sized entry must never be written
+            std::mem::forget(t);
+            self.initialized_zsts += 1;
+            return entry;
+        }
+
+        *entry.as_mut() = Entry {
+            data: ManuallyDrop::new(t),
+        };
+        let block = self.block_mut(block);
+        block.set_initialized(block.index_of(entry.as_ptr()));
+        entry
+    }
+
+    /// Marks an allocated entry as initialized.
+    fn mark_initialized(&mut self, entry: *mut Entry<T>) {
+        if Entry::<T>::IS_ZST {
+            // zero sized slots can not be told apart, an already counted one must not be
+            // counted twice
+            self.initialized_zsts = std::cmp::min(self.initialized_zsts + 1, self.in_use);
+            return;
+        }
+        let block = self.block_of(entry).expect("Entry not in Pool");
+        let block = unsafe { self.block_mut(block) };
+        block.set_initialized(block.index_of(entry));
+    }
+
+    /// Accounts for an object in a slot of policy `S` that gets dropped, taken or forgotten.
+    /// Only zero sized objects are counted here, all others are tracked by the initialized
+    /// bitmap of their block.
+    fn release_object<S: Policy>(&mut self) {
+        if Entry::<T>::IS_ZST && S::INITIALIZED {
+            self.initialized_zsts = self.initialized_zsts.saturating_sub(1);
+        }
+    }
+
+    /// Returns the pool wide index of `entry`.
+    fn index_of(&self, entry: *mut Entry<T>) -> usize {
+        let block = unsafe { self.block(self.block_of(entry).expect("Entry not in Pool")) };
+        block.base_index() + block.index_of(entry)
+    }
+
+    /// Returns the entry at the pool wide `index`.
+    fn entry_at(&self, index: usize) -> NonNull<Entry<T>> {
+        let block = self
+            .blocks
+            .partition_point(|block| block.base_index() <= index)
+            .checked_sub(1)
+            .expect("Invalid index");
+        let block = unsafe { self.block(block) };
+        let index = index - block.base_index();
+        debug_assert!(index < block.len_used(), "Invalid index");
+        debug_assert!(
+            !POISONING || !block.is_freed(index),
+            "Index refers to a freed entry"
+        );
+        block.entry_at(index)
+    }
+
+    /// Creates a handle for an initialized entry of block `block`.
+    fn handle_of(&self, block: usize, entry: NonNull<Entry<T>>) -> Handle<T> {
+        let block = unsafe { self.block(block) };
+        Handle::new(
+            entry,
+            block.serial(),
+            block.generation(block.index_of(entry.as_ptr())),
+        )
+    }
+
+    /// Returns the entry `handle` refers to when it belongs to this pool and is not stale.
+    /// Blocks without generation counters never handed out a handle, all handles into them
+    /// are foreign.
+    fn handle_entry(&self, handle: Handle<T>) -> Option<NonNull<Entry<T>>> {
+        let block = unsafe { self.block(self.block_of(handle.entry.as_ptr())?) };
+        let index = block.index_of(handle.entry.as_ptr());
+        (block.serial() == handle.block
+            && block.tracks_generations()
+            && block.generation(index) == handle.generation
+            && block.is_initialized(index))
+        .then_some(handle.entry)
+    }
+
+    /// Registers `entry` in the indirection table and returns a movable referring to it.
+    fn add_movable(&mut self, entry: NonNull<Entry<T>>) -> Movable<T> {
+        let index = if let Some(index) = self.free_movables.pop() {
+            self.movables[index as usize] = Some(entry);
+            index
+        } else {
+            let index = u32::try_from(self.movables.len())
+                .ok()
+                .filter(|&index| index != NO_MOVABLE)
+                .expect("Too many movables");
+            self.movables.push(Some(entry));
+            index
+        };
+        if !Entry::<T>::IS_ZST {
+            let block = self.block_of(entry.as_ptr()).expect("Entry not in Pool");
+            let block = unsafe { self.block_mut(block) };
+            block.set_movable(block.index_of(entry.as_ptr()), index);
+        }
+        Movable::new(index)
+    }
+
+    /// Returns the entry `movable` currently refers to.
+    fn movable_entry(&self, movable: &Movable<T>) -> NonNull<Entry<T>> {
+        self.movables
+            .get(movable.index as usize)
+            .copied()
+            .flatten()
+            .expect("Movable not from this pool")
+    }
+
+    /// Removes `movable` from the indirection table and returns its entry.
+    fn remove_movable(&mut self, movable: Movable<T>) -> NonNull<Entry<T>> {
+        let entry = self.movable_entry(&movable);
+        if !Entry::<T>::IS_ZST {
+            let block = self.block_of(entry.as_ptr()).expect("Entry not in Pool");
+            let block = unsafe { self.block_mut(block) };
+            block.clear_movable(block.index_of(entry.as_ptr()));
+        }
+        self.movables[movable.index as usize] = None;
+        self.free_movables.push(movable.index);
+        entry
+    }
+
+    /// Returns the next initialized entry at or after the (block, index) `cursor` and
+    /// advances the cursor past it.
+    fn next_initialized(&self, cursor: &mut (usize, usize)) -> Option<NonNull<Entry<T>>> {
+        next_initialized(&self.blocks, cursor)
+    }
+
+    /// Forgets about all entries, all blocks become unused. Does not drop any objects. The
+    /// observer is notified about every entry that was still in use.
+    fn reset(&mut self) {
+        self.blocks.iter_mut().for_each(Block::reset);
+        self.current_block = 0;
+        for _ in 0..self.in_use {
+            self.observer.on_free();
+        }
+        self.in_use = 0;
+        self.initialized_zsts = 0;
+        self.free_block = None;
+        self.free_blocks.clear();
+        self.movables.clear();
+        self.free_movables.clear();
+    }
+
+    /// Returns the index of the block that contains `entry`.
+    fn block_of(&self, entry: *mut Entry<T>) -> Option<usize> {
+        // find the last block that starts at or below entry
+        let pos = self
+            .blocks_by_address
+            .partition_point(|&index| unsafe { self.block(index) }.start() <= entry);
+        let index = *self.blocks_by_address.get(pos.checked_sub(1)?)?;
+
+        unsafe { self.block(index) }
+            .contains_entry(entry)
+            .then_some(index)
+    }
+
+    /// Appends a new block. The size of the block is determined by the `GrowthPolicy`.
+    fn grow(&mut self) -> Result<(), AllocError> {
+        let mut capacity = match self.blocks.last() {
+            None => self.growth_policy.first_capacity::<T>(self.min_entries)?,
+            Some(last) => self.growth_policy.next_capacity::<T>(last.capacity())?,
+        };
+        // freelists index entries within a block by 32 bits
+        capacity = std::cmp::min(capacity, u32::MAX as usize);
+
+        if self.max_entries != usize::MAX || self.max_bytes != usize::MAX {
+            // clamp the block to the configured limits
+            let stats = self.stats();
+            capacity = std::cmp::min(
+                capacity,
+                std::cmp::min(
+                    self.max_entries.saturating_sub(stats.capacity),
+                    self.max_bytes.saturating_sub(stats.bytes_reserved) / size_of::<Entry<T>>(),
+                ),
+            );
+            if capacity == 0 {
+                return Err(AllocError::CapacityExceeded);
+            }
+        }
+
+        self.blocks
+            .try_reserve(1)
+            .map_err(|_| AllocError::OutOfMemory)?;
+        self.blocks_by_address
+            .try_reserve(1)
+            .map_err(|_| AllocError::OutOfMemory)?;
+
+        let base_index = self
+            .blocks
+            .last()
+            .map_or(0, |last| last.base_index() + last.capacity());
+        let block = Block::new(capacity, base_index, self.allocator, &self.observer)?;
+        let pos = self
+            .blocks_by_address
+            .partition_point(|&index| unsafe { self.block(index) }.start() < block.start());
+        self.blocks_by_address.insert(pos, self.blocks.len());
+        self.blocks.push(block);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            entry_type = std::any::type_name::<T>(),
+            blocks = self.blocks.len(),
+            capacity = self.blocks.iter().map(Block::capacity).sum::<usize>(),
+            "pool grown"
+        );
+        Ok(())
+    }
+
+    /// Makes sure that the next `additional` allocations will not need to allocate a new
+    /// block.
+    fn reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+        if Entry::<T>::IS_ZST {
+            return Ok(());
+        }
+        let mut available = self.stats().capacity - self.in_use;
+        while available < additional {
+            self.grow()?;
+            available += unsafe { self.blocks.last().unwrap_unchecked() }.capacity();
+        }
+        Ok(())
+    }
+
+    /// Returns a reference to an allocated block.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be less than `blocks.len()`.
+    #[inline]
+    unsafe fn block(&self, index: usize) -> &Block<T> {
+        self.blocks.get_unchecked(index)
+    }
+
+    /// Returns a mutable reference to an allocated block.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be less than `blocks.len()`.
+    #[inline]
+    unsafe fn block_mut(&mut self, index: usize) -> &mut Block<T> {
+        self.blocks.get_unchecked_mut(index)
+    }
+
+    /// Releases all trailing blocks that have no entries in use.
+    fn shrink_to_fit(&mut self) {
+        let mut keep = self.blocks.len();
+        while keep > 0 && unsafe { self.block(keep - 1) }.live() == 0 {
+            keep -= 1;
+        }
+
+        if keep == self.blocks.len() {
+            return;
+        }
+
+        // the free entries of the released blocks go away with their freelists
+        for block in &self.blocks[keep..] {
+            self.observer.on_block_free(block.capacity(), block.size());
+        }
+        self.blocks.truncate(keep);
+        self.blocks_by_address.retain(|&index| index < keep);
+        self.current_block = std::cmp::min(self.current_block, keep.saturating_sub(1));
+        self.rebuild_free_blocks();
+    }
+
+    /// Moves the objects of movables from higher blocks into lower blocks and releases the
+    /// blocks that became empty by this.
+    fn compact(&mut self) -> usize {
+        if Entry::<T>::IS_ZST {
+            return 0;
+        }
+
+        let mut target = 0;
+        let mut emptied = Vec::new();
+        for block in (1..self.blocks.len()).rev() {
+            // only blocks whose entries in use are all movable can become empty
+            let block_ref = unsafe { self.block(block) };
+            let live = block_ref.live();
+            if live == 0 || live != block_ref.movables_len() {
+                continue;
+            }
+            let free_below: usize = self.blocks[..block]
+                .iter()
+                .map(|block| block.capacity() - block.live())
+                .sum();
+            if free_below < live {
+                continue;
+            }
+
+            for old_index in 0..block_ref.len_used() {
+                let movable = match unsafe { self.block_mut(block) }.clear_movable(old_index) {
+                    Some(movable) => movable,
+                    None => continue,
+                };
+                while {
+                    let target = unsafe { self.block(target) };
+                    !target.has_free() && target.is_full()
+                } {
+                    target += 1;
+                }
+                debug_assert!(target < block);
+
+                unsafe {
+                    let target_block = self.block_mut(target);
+                    let new = match target_block.pop_free::<L>() {
+                        Some(entry) => entry,
+                        None => target_block.extend(),
+                    };
+                    let new_index = target_block.index_of(new.as_ptr());
+                    target_block.set_initialized(new_index);
+                    target_block.set_movable(new_index, movable);
+
+                    let old_block = self.block_mut(block);
+                    let old = old_block.entry_at(old_index);
+                    std::ptr::copy_nonoverlapping(old.as_ptr(), new.as_ptr(), 1);
+                    old_block.dec_live();
+                    old_block.clear_initialized(old_index);
+                    old_block.inc_generation(old_index);
+                    if POISONING {
+                        old_block.set_freed(old_index);
+                        Entry::poison(old.as_ptr());
+                    }
+
+                    self.movables[movable as usize] = Some(new);
+                }
+            }
+            emptied.push(block);
+        }
+
+        self.release_blocks(&emptied)
+    }
+
+    /// Releases the blocks at the indices in `blocks`, which must have no entries in use.
+    /// Returns the number of bytes released. Unlike `shrink_to_fit()` this removes blocks in
+    /// the middle as well.
+    fn release_blocks(&mut self, blocks: &[usize]) -> usize {
+        if blocks.is_empty() {
+            return 0;
+        }
+
+        // the free entries of the released blocks go away with their freelists, the
+        // remaining blocks keep their base index
+        let mut released = 0;
+        let mut index = 0;
+        let observer = &self.observer;
+        self.blocks.retain(|block| {
+            index += 1;
+            if !blocks.contains(&(index - 1)) {
+                return true;
+            }
+            debug_assert_eq!(block.live(), 0);
+            observer.on_block_free(block.capacity(), block.size());
+            released += block.size();
+            false
+        });
+        self.blocks_by_address = (0..self.blocks.len()).collect();
+        self.blocks_by_address
+            .sort_unstable_by_key(|&index| self.blocks[index].start());
+        self.current_block = self
+            .blocks
+            .iter()
+            .position(|block| !block.is_full())
+            .unwrap_or(self.blocks.len().saturating_sub(1));
+        self.rebuild_free_blocks();
+        released
+    }
+
+    /// Releases the pages of all blocks that contain only free entries.
+    #[cfg(target_os = "linux")]
+    fn release_free_pages(&mut self) -> usize {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        self.blocks
+            .iter_mut()
+            .map(|block| block.release_free_pages::<L>(page_size))
+            .sum()
+    }
+
+    /// Collects the statistics of this pool.
+    fn stats(&self) -> PoolStats {
+        let mut stats = PoolStats {
+            in_use: self.in_use,
+            high_water: self.high_water,
+            blocks_allocated: self.blocks.len(),
+            bytes_used: if Entry::<T>::IS_ZST {
+                0
+            } else {
+                self.in_use * size_of::<Entry<T>>()
+            },
+            ..PoolStats::default()
+        };
+
+        let mut len_used = 0;
+        for block in &self.blocks {
+            stats.capacity += block.capacity();
+            stats.bytes_reserved += block.size();
+            stats.huge_page_advised_blocks += usize::from(block.is_huge_page_advised());
+            len_used += block.len_used();
+        }
+
+        // Every entry ever handed out from a block is either in use or in the freelist.
+        if !Entry::<T>::IS_ZST {
+            stats.freelist_len = len_used - self.in_use;
+        }
+        stats
+    }
+}
+
+/// Returns the next initialized entry of `blocks` at or after the (block, index) `cursor` and
+/// advances the cursor past it.
+fn next_initialized<T>(
+    blocks: &[Block<T>],
+    cursor: &mut (usize, usize),
+) -> Option<NonNull<Entry<T>>> {
+    while let Some(block) = blocks.get(cursor.0) {
+        if let Some(index) = block.next_initialized(cursor.1) {
+            cursor.1 = index + 1;
+            return Some(block.entry_at(index));
+        }
+        *cursor = (cursor.0 + 1, 0);
+    }
+    None
+}
+
+impl<T, L: FreelistStrategy, O: PoolObserver> Drop for PoolInner<T, L, O> {
+    fn drop(&mut self) {
+        if self.drop_live {
+            let mut cursor = (0, 0);
+            while let Some(entry) = self.next_initialized(&mut cursor) {
+                unsafe { ManuallyDrop::drop(&mut *Entry::data_ptr(entry)) };
+            }
+            for _ in 0..self.initialized_zsts {
+                unsafe { ManuallyDrop::drop(&mut *Entry::data_ptr(Entry::<T>::zst())) };
+            }
+            for _ in 0..self.in_use {
+                self.observer.on_free();
+            }
+        }
+
+        if self.drop_live || self.in_use == 0 {
+            for block in &self.blocks {
+                self.observer.on_block_free(block.capacity(), block.size());
+            }
+            return;
+        }
+
+        // Slots still point into the blocks, they must never be deallocated.
+        let blocks = std::mem::take(&mut self.blocks);
+        let leaked_bytes: usize = blocks.iter().map(Block::size).sum();
+        std::mem::forget(blocks);
+
+        if self.leak_policy == LeakPolicy::Leak {
+            return;
+        }
+
+        // with tracing this is the report of `LogAndLeak` as well
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            entry_type = std::any::type_name::<T>(),
+            in_use = self.in_use,
+            leaked_bytes,
+            "pool dropped while entries are still in use, leaking its blocks"
+        );
+
+        #[cfg(not(feature = "tracing"))]
+        if self.leak_policy == LeakPolicy::LogAndLeak {
+            eprintln!(
+                "Pool dropped while {} entries are still in use, leaking {} bytes",
+                self.in_use, leaked_bytes
+            );
+        }
+
+        // Panicking while already unwinding would abort.
+        if self.leak_policy == LeakPolicy::Panic && !std::thread::panicking() {
+            panic!(
+                "Pool dropped while {} entries are still in use",
+                self.in_use
+            );
+        }
+    }
+}
+
+impl<T, L: FreelistStrategy, O: PoolObserver> fmt::Debug for PoolInner<T, L, O> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("PoolInner")
+            .field("blocks", &self.blocks)
+            .field("current_block", &self.current_block)
+            .field("min_entries", &self.min_entries)
+            .field("growth_policy", &self.growth_policy)
+            .field("max_entries", &self.max_entries)
+            .field("max_bytes", &self.max_bytes)
+            .field("leak_policy", &self.leak_policy)
+            .field("drop_live", &self.drop_live)
+            .field("in_use", &self.in_use)
+            .field("high_water", &self.high_water)
+            .field("freelist_len", &self.stats().freelist_len)
+            .finish()
+    }
+}
+
+/// Statistics about a pool, obtained by `PoolApi::stats()`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Number of entries currently allocated.
+    pub in_use: usize,
+    /// Total number of entries all blocks can hold.
+    pub capacity: usize,
+    /// Number of blocks allocated.
+    pub blocks_allocated: usize,
+    /// Number of entries in the freelist.
+    pub freelist_len: usize,
+    /// The highest number of entries that were ever in use at the same time.
+    pub high_water: usize,
+    /// Bytes of memory allocated for all blocks.
+    pub bytes_reserved: usize,
+    /// Bytes of memory occupied by entries in use.
+    pub bytes_used: usize,
+    /// Number of blocks for which huge pages were requested successfully, see
+    /// `HugePageBlockAllocator`. These are mapped with `MAP_HUGETLB` or advised with
+    /// `MADV_HUGEPAGE`. Transparent huge pages are only a hint, the kernel may still back
+    /// advised blocks by normal pages. `AnonHugePages` in `/proc/self/smaps` shows the
+    /// actual backing. `PoolApi::huge_page_advised_blocks()` tells which blocks these are.
+    pub huge_page_advised_blocks: usize,
+}
+
+/// Iterator over the live initialized objects of a pool, obtained by `PoolApi::iter_live()`.
+pub struct LiveIter<'a, T> {
+    blocks: &'a [Block<T>],
+    cursor: (usize, usize),
+}
+
+impl<'a, T> Iterator for LiveIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        next_initialized(self.blocks, &mut self.cursor)
+            .map(|entry| unsafe { &*entry.as_ref().data })
+    }
+}
+
+/// What happens when a pool gets dropped while entries are still in use. Dropping the
+/// memory in this case would leave dangling slots behind, thus the blocks of the pool are
+/// always leaked. Configured by `PoolApi::with_leak_policy()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LeakPolicy {
+    /// Leak the blocks and panic. This is the default in debug builds.
+    Panic,
+    /// Silently leak the blocks. This is the default in release builds.
+    Leak,
+    /// Leak the blocks and report it. With the `tracing` feature this is a warning event,
+    /// otherwise a message printed to stderr.
+    LogAndLeak,
+}
+
+impl LeakPolicy {
+    const fn new() -> Self {
+        if cfg!(debug_assertions) {
+            LeakPolicy::Panic
+        } else {
+            LeakPolicy::Leak
+        }
+    }
+}
+
+impl Default for LeakPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod pool_tests {
+    use crate::*;
+
+    #[test]
+    fn smoke() {
+        let _pool: Pool<String> = Pool::new();
+    }
+
+    #[test]
+    fn leak() {
+        let pool: Pool<u64> = Pool::new();
+        let _ = pool.alloc(1234);
         pool.leak();
     }
+
+    #[test]
+    fn stats() {
+        let pool: Pool<u64> = Pool::new();
+        assert_eq!(pool.stats(), PoolStats::default());
+
+        let mut slots = Vec::new();
+        for i in 0..100 {
+            slots.push(pool.alloc(i));
+        }
+        unsafe {
+            slots.drain(0..40).for_each(|slot| pool.free(slot));
+        }
+
+        let stats = pool.stats();
+        assert_eq!(stats.in_use, 60);
+        assert_eq!(stats.high_water, 100);
+        assert_eq!(stats.blocks_allocated, 2);
+        assert_eq!(stats.freelist_len, 40);
+        assert!(stats.capacity >= 100);
+        assert!(stats.bytes_reserved >= stats.bytes_used);
+        assert_eq!(stats.bytes_used, 60 * std::mem::size_of::<Entry<u64>>());
+        assert_eq!(stats.huge_page_advised_blocks, 0);
+        assert_eq!(pool.huge_page_advised_blocks(), vec![false, false]);
+
+        unsafe {
+            slots.drain(..).for_each(|slot| pool.free(slot));
+        }
+    }
+
+    #[test]
+    fn shrink_to_fit() {
+        let pool: Pool<u64> = Pool::new();
+
+        let mut slots = Vec::new();
+        for i in 0..1000 {
+            slots.push(pool.alloc(i));
+        }
+        let blocks = pool.stats().blocks_allocated;
+        assert!(blocks > 2);
+
+        // keep the first entry alive, the first block stays
+        unsafe {
+            slots.drain(1..).for_each(|slot| pool.free(slot));
+        }
+        pool.shrink_to_fit();
+
+        let stats = pool.stats();
+        assert_eq!(stats.blocks_allocated, 1);
+        assert_eq!(stats.in_use, 1);
+        assert_eq!(stats.freelist_len, stats.capacity - 1);
+
+        // the pool is still usable after shrinking
+        for i in 0..1000 {
+            slots.push(pool.alloc(i));
+        }
+        assert_eq!(pool.stats().blocks_allocated, blocks);
+
+        unsafe {
+            slots.drain(..).for_each(|slot| pool.free(slot));
+        }
+        pool.shrink_to_fit();
+        assert_eq!(
+            pool.stats(),
+            PoolStats {
+                high_water: 1001,
+                ..PoolStats::default()
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn release_free_pages() {
+        let pool: Pool<u64> = Pool::new();
+        pool.with_growth_policy(GrowthPolicy::Fixed { entries: 8192 });
+        let mut slots: Vec<_> = (0..8192).map(|i| pool.alloc(i)).collect();
+        assert_eq!(pool.release_free_pages(), 0);
+
+        // free the middle of the block
+        let middle: Vec<_> = slots.drain(1000..7000).collect();
+        unsafe { pool.free_many(middle) };
+        let released = pool.release_free_pages();
+        assert!(released > 0);
+        assert!(released <= 6000 * std::mem::size_of::<Entry<u64>>());
+        // nothing new to release
+        assert_eq!(pool.release_free_pages(), 0);
+
+        let stats = pool.stats();
+        assert_eq!(stats.freelist_len, 6000);
+        assert_eq!(stats.blocks_allocated, 1);
+
+        // freelist stays valid and released entries are reused
+        slots.extend((0..6000).map(|i| pool.alloc(i)));
+        assert_eq!(pool.stats().blocks_allocated, 1);
+        assert!(slots.iter().all(|slot| *slot.get() < 8192));
+        let mut addrs: Vec<_> = slots.iter().map(|slot| slot.get() as *const u64).collect();
+        addrs.sort_unstable();
+        addrs.dedup();
+        assert_eq!(addrs.len(), 8192);
+
+        unsafe { pool.free_many(slots) };
+    }
+
+    #[test]
+    fn reserve() {
+        let pool: Pool<u64> = Pool::new();
+        pool.reserve(1000);
+
+        let stats = pool.stats();
+        assert!(stats.capacity >= 1000);
+        assert_eq!(stats.in_use, 0);
+
+        let mut slots = Vec::new();
+        for i in 0..1000 {
+            slots.push(pool.alloc(i));
+        }
+        assert_eq!(pool.stats().blocks_allocated, stats.blocks_allocated);
+
+        // reserving what is already available is a no-op
+        pool.reserve(stats.capacity - 1000);
+        assert_eq!(pool.stats().blocks_allocated, stats.blocks_allocated);
+
+        unsafe {
+            slots.drain(..).for_each(|slot| pool.free(slot));
+        }
+    }
+
+    #[test]
+    fn try_alloc() {
+        let pool: Pool<u64> = Pool::new();
+        let slot = pool.try_alloc(1234).unwrap();
+        assert_eq!(slot.get(), &1234);
+        unsafe {
+            pool.free(slot);
+        }
+
+        let pool: Pool<u64> = Pool::new();
+        pool.with_min_entries(usize::MAX / 2);
+        assert_eq!(pool.try_alloc(1234).err(), Some(AllocError::LayoutOverflow));
+        assert_eq!(
+            pool.try_alloc_uninit().err(),
+            Some(AllocError::LayoutOverflow)
+        );
+
+        // blocks are limited to 2^32 entries, use large entries to exceed the memory
+        let pool: Pool<[u64; 1 << 30]> = Pool::new();
+        pool.with_min_entries(1 << 20);
+        assert_eq!(pool.try_alloc_uninit().err(), Some(AllocError::OutOfMemory));
+        assert_eq!(pool.stats(), PoolStats::default());
+    }
+
+    #[test]
+    fn max_entries() {
+        let pool: Pool<u64> = Pool::new();
+        pool.with_max_entries(100);
+
+        let mut slots = Vec::new();
+        for i in 0..100 {
+            slots.push(pool.try_alloc(i).unwrap());
+        }
+        assert_eq!(
+            pool.try_alloc(100).err(),
+            Some(AllocError::CapacityExceeded)
+        );
+        assert_eq!(pool.stats().capacity, 100);
+
+        // freed entries can be reused
+        unsafe {
+            pool.free(slots.pop().unwrap());
+        }
+        slots.push(pool.try_alloc(100).unwrap());
+        assert_eq!(
+            pool.try_alloc(101).err(),
+            Some(AllocError::CapacityExceeded)
+        );
+
+        unsafe {
+            slots.drain(..).for_each(|slot| pool.free(slot));
+        }
+    }
+
+    #[test]
+    fn max_bytes() {
+        let pool: Pool<u64> = Pool::new();
+        let entry_size = std::mem::size_of::<Entry<u64>>();
+        pool.with_max_bytes(300 * entry_size);
+
+        let mut slots = Vec::new();
+        for i in 0..300 {
+            slots.push(pool.try_alloc(i).unwrap());
+        }
+        assert_eq!(
+            pool.try_alloc(300).err(),
+            Some(AllocError::CapacityExceeded)
+        );
+        assert_eq!(pool.stats().bytes_reserved, 300 * entry_size);
+
+        unsafe {
+            slots.drain(..).for_each(|slot| pool.free(slot));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "still in use")]
+    fn drop_in_use_panics() {
+        let pool: Pool<u64> = Pool::new();
+        pool.with_leak_policy(LeakPolicy::Panic);
+        let _ = pool.alloc(1234);
+        drop(pool);
+    }
+
+    #[test]
+    fn drop_in_use_leaks() {
+        let pool: Pool<u64> = Pool::new();
+        pool.with_leak_policy(LeakPolicy::Leak);
+        let slot = pool.alloc(1234);
+        drop(pool);
+        // the memory is leaked, thus the slot stays valid
+        assert_eq!(*slot.get(), 1234);
+    }
+
+    #[test]
+    fn drop_in_use_logs_and_leaks() {
+        let pool: Pool<u64> = Pool::new();
+        pool.with_leak_policy(LeakPolicy::LogAndLeak);
+        let slot = pool.alloc(1234);
+        drop(pool);
+        assert_eq!(*slot.get(), 1234);
+    }
+
+    /// Counts the warning events emitted while it is the default subscriber.
+    #[cfg(feature = "tracing")]
+    struct WarnCounter(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for WarnCounter {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            if *event.metadata().level() == tracing::Level::WARN {
+                self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn leak_reports() {
+        let warnings = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let count = || warnings.load(std::sync::atomic::Ordering::Relaxed);
+        tracing::subscriber::with_default(WarnCounter(warnings.clone()), || {
+            let pool: Pool<u64> = Pool::new();
+            pool.with_leak_policy(LeakPolicy::Leak);
+            let _ = pool.alloc(1234);
+            drop(pool);
+            assert_eq!(count(), 0);
+
+            let pool: Pool<u64> = Pool::new();
+            pool.with_leak_policy(LeakPolicy::LogAndLeak);
+            let _ = pool.alloc(1234);
+            drop(pool);
+            assert_eq!(count(), 1);
+        });
+    }
+
+    #[test]
+    fn drop_unused() {
+        let pool: Pool<u64> = Pool::new();
+        pool.with_leak_policy(LeakPolicy::Panic);
+        let slot = pool.alloc(1234);
+        unsafe { pool.free(slot) };
+        drop(pool);
+    }
+
+    struct DropCounter<'a>(&'a std::cell::Cell<usize>);
+
+    impl Drop for DropCounter<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn clear() {
+        let drops = std::cell::Cell::new(0);
+        let pool: Pool<DropCounter> = Pool::new();
+
+        let _a = pool.alloc(DropCounter(&drops));
+        let b = pool.alloc(DropCounter(&drops));
+        let _c = pool.alloc_uninit();
+        let mut d = pool.alloc_uninit();
+        d.get_uninit().write(DropCounter(&drops));
+        let _d = unsafe { pool.assume_init(d) };
+        unsafe { pool.free(b) };
+        assert_eq!(drops.get(), 1);
+
+        unsafe { pool.clear() };
+        assert_eq!(drops.get(), 3);
+        assert_eq!(pool.stats().in_use, 0);
+        assert_eq!(pool.stats().freelist_len, 0);
+        assert_eq!(pool.stats().blocks_allocated, 1);
+
+        let e = pool.alloc(DropCounter(&drops));
+        unsafe { pool.free(e) };
+        assert_eq!(drops.get(), 4);
+    }
+
+    #[test]
+    fn drop_live() {
+        let drops = std::cell::Cell::new(0);
+        let pool: Pool<DropCounter> = Pool::new();
+        unsafe { pool.with_drop_live(true) };
+
+        for _ in 0..100 {
+            let _ = pool.alloc(DropCounter(&drops));
+        }
+        let _ = pool.alloc_uninit();
+        drop(pool);
+        assert_eq!(drops.get(), 100);
+    }
+
+    thread_local! {
+        static ZST_DROPS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    struct ZstDropCounter;
+
+    impl Drop for ZstDropCounter {
+        fn drop(&mut self) {
+            ZST_DROPS.with(|drops| drops.set(drops.get() + 1));
+        }
+    }
+
+    #[derive(Default)]
+    struct FreeCounter(std::cell::Cell<usize>);
+
+    impl PoolObserver for FreeCounter {
+        fn on_free(&self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn clear_zero_sized() {
+        ZST_DROPS.with(|drops| drops.set(0));
+        let frees = FreeCounter::default();
+        let pool: Pool<ZstDropCounter, Ordered, &FreeCounter> = Pool::with_observer(&frees);
+
+        let _a = pool.alloc(ZstDropCounter);
+        let b = pool.alloc(ZstDropCounter);
+        let _c = pool.alloc_uninit();
+        let _d = pool.init(pool.alloc_uninit(), ZstDropCounter);
+        let e = pool.alloc_uninit();
+        unsafe { pool.free(b) };
+        unsafe { pool.free(e) };
+        assert_eq!(ZST_DROPS.with(std::cell::Cell::get), 1);
+        assert_eq!(frees.0.get(), 2);
+
+        unsafe { pool.clear() };
+        assert_eq!(ZST_DROPS.with(std::cell::Cell::get), 3);
+        assert_eq!(frees.0.get(), 5);
+        assert_eq!(pool.stats().in_use, 0);
+    }
+
+    #[test]
+    fn drop_live_zero_sized() {
+        ZST_DROPS.with(|drops| drops.set(0));
+        let frees = FreeCounter::default();
+        let pool: Pool<ZstDropCounter, Ordered, &FreeCounter> = Pool::with_observer(&frees);
+        unsafe { pool.with_drop_live(true) };
+
+        for _ in 0..100 {
+            let _ = pool.alloc(ZstDropCounter);
+        }
+        let _ = pool.alloc_uninit();
+        drop(pool);
+        assert_eq!(ZST_DROPS.with(std::cell::Cell::get), 100);
+        assert_eq!(frees.0.get(), 101);
+    }
+
+    #[test]
+    fn clear_notifies_observer() {
+        let frees = FreeCounter::default();
+        let pool: Pool<u64, Ordered, &FreeCounter> = Pool::with_observer(&frees);
+        let _slots = pool.alloc_many(0..10);
+        let _uninit = pool.alloc_uninit();
+        unsafe { pool.clear() };
+        assert_eq!(frees.0.get(), 11);
+
+        let pool: Pool<u64, Ordered, &FreeCounter> = Pool::with_observer(&frees);
+        unsafe { pool.with_drop_live(true) };
+        let _slots = pool.alloc_many(0..10);
+        let _uninit = pool.alloc_uninit();
+        drop(pool);
+        assert_eq!(frees.0.get(), 22);
+    }
+
+    #[test]
+    fn live_objects() {
+        let pool: Pool<u64> = Pool::new();
+        let mut slots: Vec<_> = (0..200).map(|i| pool.alloc(i)).collect();
+        let _uninit = pool.alloc_uninit();
+
+        unsafe {
+            pool.free(slots.swap_remove(0));
+            pool.for_each_live_mut(|value| *value *= 2);
+
+            let mut sum = 0;
+            pool.for_each_live(|value| sum += value);
+            assert_eq!(sum, (1..200).sum::<u64>() * 2);
+
+            pool.retain(|value| *value % 4 == 0);
+            assert_eq!(pool.iter_live(|iter| iter.count()), 99);
+            assert!(pool.iter_live(|mut iter| iter.all(|value| value % 4 == 0)));
+            assert_eq!(pool.stats().in_use, 100);
+
+            pool.clear();
+        }
+    }
+
+    #[test]
+    fn index_round_trip() {
+        let pool: Pool<u64> = Pool::new();
+        let slots: Vec<_> = (0..1000).map(|i| pool.alloc(i)).collect();
+        assert!(pool.stats().blocks_allocated > 1);
+
+        let indices: Vec<u32> = slots
+            .into_iter()
+            .map(|slot| slot.into_index(&pool))
+            .collect();
+        for (i, index) in indices.iter().enumerate() {
+            assert_eq!(*index, i as u32);
+            let slot: Slot<u64, Initialized> = unsafe { pool.slot_from_index(*index) };
+            assert_eq!(*slot.get(), i as u64);
+            assert_eq!(slot.into_index(&pool), *index);
+        }
+
+        for index in indices {
+            unsafe { pool.free(pool.slot_from_index::<Initialized>(index)) };
+        }
+    }
+
+    #[test]
+    fn alloc_with() {
+        let pool: Pool<[u64; 4096]> = Pool::new();
+        let slot = pool.alloc_with(|| [42; 4096]);
+        assert!(slot.get().iter().all(|&v| v == 42));
+
+        // initialized through the pool, thus visible to iteration
+        assert_eq!(unsafe { pool.iter_live(|iter| iter.count()) }, 1);
+        unsafe { pool.free(slot) };
+
+        let pool: Pool<u64> = Pool::new();
+        pool.with_max_entries(0);
+        assert_eq!(
+            pool.try_alloc_with(|| unreachable!()).err(),
+            Some(AllocError::CapacityExceeded)
+        );
+    }
+
+    #[test]
+    fn slot_init() {
+        let pool: Pool<String> = Pool::new();
+        let slot = pool.init(pool.alloc_uninit(), String::from("Hello Init"));
+        assert_eq!(slot.get(), "Hello Init");
+        // initialized through the pool, thus visible to iteration
+        assert_eq!(unsafe { pool.iter_live(|iter| iter.count()) }, 1);
+        unsafe { pool.free(slot) };
+    }
+
+    #[test]
+    fn zero_sized() {
+        let pool: Pool<()> = Pool::new();
+        let mut slots = Vec::new();
+        for _ in 0..1000 {
+            slots.push(pool.alloc(()));
+        }
+        slots.push(pool.alloc_with(|| ()));
+        slots.extend(pool.alloc_many(std::iter::repeat_n((), 100)));
+
+        let stats = pool.stats();
+        assert_eq!(stats.in_use, 1101);
+        assert_eq!(stats.blocks_allocated, 0);
+        assert_eq!(stats.bytes_reserved, 0);
+        assert_eq!(stats.bytes_used, 0);
+        assert_eq!(stats.freelist_len, 0);
+
+        // uninitialized slots share one entry, they hand out zero sized references only
+        let mut a = pool.alloc_uninit();
+        let mut b = pool.alloc_uninit();
+        let (a_mem, b_mem) = (a.get_uninit(), b.get_uninit());
+        assert_eq!(std::mem::size_of_val(a_mem), 0);
+        a_mem.write(());
+        b_mem.write(());
+        slots.push(unsafe { pool.assume_init(a) });
+        slots.push(unsafe { pool.assume_init(b) });
+        assert_eq!(pool.stats().in_use, 1103);
+        unsafe { pool.free_many(slots.drain(1101..)) };
+
+        let tail = slots.split_off(1000);
+        unsafe {
+            pool.free_many(tail);
+            slots.drain(..).for_each(|slot| pool.free(slot));
+        }
+        assert_eq!(pool.stats().in_use, 0);
+        assert_eq!(pool.stats().high_water, 1103);
+
+        // destructors of zero sized types still run
+        static DROPS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        struct Zst;
+        impl Drop for Zst {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+        let pool: Pool<Zst> = Pool::new();
+        let slot = pool.alloc(Zst);
+        assert_eq!(DROPS.load(std::sync::atomic::Ordering::Relaxed), 0);
+        unsafe { pool.free(slot) };
+        assert_eq!(DROPS.load(std::sync::atomic::Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn over_aligned() {
+        #[repr(align(64))]
+        struct Align64(u8);
+        #[repr(align(4096))]
+        struct Align4096(u8);
+
+        let pool: Pool<Align64> = Pool::new();
+        let slots: Vec<_> = (0..1000).map(|i| pool.alloc(Align64(i as u8))).collect();
+        assert!(slots
+            .iter()
+            .enumerate()
+            .all(|(i, slot)| slot.get().0 == i as u8));
+        assert!(slots
+            .iter()
+            .all(|slot| (slot.get() as *const _ as usize).is_multiple_of(64)));
+        unsafe { pool.free_many(slots) };
+
+        let pool: Pool<Align4096> = Pool::new();
+        let slots: Vec<_> = (0..100).map(|i| pool.alloc(Align4096(i as u8))).collect();
+        assert!(pool.stats().blocks_allocated > 1);
+        assert!(slots
+            .iter()
+            .enumerate()
+            .all(
+                |(i, slot)| (slot.get() as *const _ as usize).is_multiple_of(4096)
+                    && slot.get().0 == i as u8
+            ));
+        unsafe { pool.free_many(slots) };
+    }
+
+    #[test]
+    #[should_panic(expected = "zero sized")]
+    fn zero_sized_handle() {
+        let pool: Pool<()> = Pool::new();
+        let _ = pool.alloc_handle(());
+    }
+
+    #[test]
+    fn alloc_free_many() {
+        let pool: Pool<u64> = Pool::new();
+        let mut slots = pool.alloc_many(0..1000);
+        assert_eq!(pool.stats().in_use, 1000);
+        assert!(slots
+            .iter()
+            .enumerate()
+            .all(|(i, slot)| *slot.get() == i as u64));
+
+        // free in some scrambled order, the freelist must end up sorted
+        slots.reverse();
+        let (even, odd): (Vec<_>, Vec<_>) =
+            slots.into_iter().enumerate().partition(|(i, _)| i % 2 == 0);
+        unsafe {
+            pool.free_many(odd.into_iter().chain(even).map(|(_, slot)| slot));
+        }
+        assert_eq!(pool.stats().in_use, 0);
+
+        pool.with_lock(|pool| {
+            for block in &pool.blocks {
+                let freelist = block.freelist();
+                assert_eq!(freelist.len(), block.len_used());
+                // a sorted cyclic list wraps around once
+                let descents = freelist
+                    .iter()
+                    .zip(freelist.iter().cycle().skip(1))
+                    .filter(|(this, next)| next < this)
+                    .count();
+                assert_eq!(descents, 1);
+            }
+        });
+
+        let uninit = pool.alloc_many_uninit(500);
+        assert_eq!(pool.stats().in_use, 500);
+        unsafe { pool.free_many(uninit) };
+    }
+
+    #[test]
+    #[should_panic(expected = "freed entry")]
+    #[cfg(any(debug_assertions, feature = "poison"))]
+    fn use_after_free() {
+        let pool: Pool<[u64; 4]> = Pool::new();
+        let slot = pool.alloc([1, 2, 3, 4]).for_mutation();
+        let stale = unsafe { slot.copy() };
+        unsafe { pool.free(slot) };
+        let _ = stale.get();
+    }
+
+    #[test]
+    #[should_panic(expected = "freed entry")]
+    #[cfg(any(debug_assertions, feature = "poison"))]
+    fn use_after_free_small() {
+        let pool: Pool<u8> = Pool::new();
+        let slot = pool.alloc(1).for_mutation();
+        let stale = unsafe { slot.copy() };
+        unsafe { pool.free(slot) };
+        let _ = stale.get();
+    }
+
+    #[test]
+    #[should_panic(expected = "already freed")]
+    #[cfg(any(debug_assertions, feature = "poison"))]
+    fn double_free() {
+        let pool: Pool<u64> = Pool::new();
+        let slot = pool.alloc(1).for_mutation();
+        let copy = unsafe { slot.copy() };
+        unsafe {
+            pool.free(slot);
+            pool.free(copy);
+        }
+    }
+
+    #[test]
+    fn poison_pattern_in_live_object() {
+        // live objects that look like poison are not mistaken for freed ones
+        let pool: Pool<[u8; 16]> = Pool::new();
+        let slot = pool.alloc([0xDE, 0xAD, 0xBE, 0xEF].repeat(4).try_into().unwrap());
+        unsafe { pool.free(slot) };
+        let slot = pool.alloc([0xDE, 0xAD, 0xBE, 0xEF].repeat(4).try_into().unwrap());
+        assert_eq!(slot.get()[15], 0xEF);
+        unsafe { pool.free(slot) };
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid index")]
+    #[cfg(debug_assertions)]
+    fn invalid_index() {
+        let pool: Pool<u64> = Pool::new();
+        pool.with_leak_policy(LeakPolicy::Leak);
+        let _ = pool.alloc(1);
+        let _: Slot<u64, Initialized> = unsafe { pool.slot_from_index(10) };
+    }
+
+    #[test]
+    #[should_panic(expected = "Index refers to a freed entry")]
+    #[cfg(debug_assertions)]
+    fn freed_index() {
+        let pool: Pool<u64> = Pool::new();
+        let _a = pool.alloc(1);
+        let b = pool.alloc(2);
+        let index = b.into_index(&pool);
+        let b: Slot<u64, Initialized> = unsafe { pool.slot_from_index(index) };
+        unsafe { pool.free(b) };
+        pool.with_leak_policy(LeakPolicy::Leak);
+        let _: Slot<u64, Initialized> = unsafe { pool.slot_from_index(index) };
+    }
 }
 
 #[cfg(test)]
@@ -367,4 +2431,113 @@ mod tpool_tests {
         let _ = pool.alloc(1234);
         pool.leak();
     }
+
+    #[test]
+    fn max_entries() {
+        let pool: TPool<u64> = TPool::new();
+        pool.with_max_entries(1);
+
+        let slot = pool.try_alloc(1).unwrap();
+        assert_eq!(pool.try_alloc(2).err(), Some(AllocError::CapacityExceeded));
+        unsafe {
+            pool.free(slot);
+        }
+    }
+
+    #[test]
+    fn alloc_many_failure() {
+        let pool: TPool<u64> = TPool::new();
+        pool.with_max_entries(2);
+
+        assert_eq!(
+            pool.try_alloc_many(0..3).err(),
+            Some(AllocError::CapacityExceeded)
+        );
+        assert_eq!(
+            pool.try_alloc_many_uninit(3).err(),
+            Some(AllocError::CapacityExceeded)
+        );
+        assert_eq!(pool.stats().in_use, 0);
+
+        // a failing allocation must not poison the mutex
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| pool.alloc_many(0..3)));
+        assert!(result.is_err());
+        let slots = pool.alloc_many(0..2);
+        assert_eq!(pool.stats().in_use, 2);
+        unsafe { pool.free_many(slots) };
+    }
+
+    #[test]
+    fn alloc_blocking() {
+        let pool: TPool<u64> = TPool::new();
+        pool.with_max_entries(1);
+
+        let slot = pool.alloc(1);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                unsafe {
+                    pool.free(slot);
+                }
+            });
+
+            let slot = pool.alloc_blocking(2);
+            assert_eq!(slot.get(), &2);
+            unsafe {
+                pool.free(slot);
+            }
+        });
+    }
+
+    #[test]
+    fn alloc_timeout() {
+        let pool: TPool<u64> = TPool::new();
+        pool.with_max_entries(1);
+
+        let slot = pool.alloc(1);
+        assert_eq!(
+            pool.alloc_timeout(2, std::time::Duration::from_millis(10))
+                .err(),
+            Some(AllocError::CapacityExceeded)
+        );
+        unsafe {
+            pool.free(slot);
+        }
+
+        let slot = pool
+            .alloc_timeout(3, std::time::Duration::from_millis(10))
+            .unwrap();
+        unsafe {
+            pool.free(slot);
+        }
+    }
+
+    #[test]
+    fn retain_wakes_all() {
+        let pool: TPool<u64> = TPool::new();
+        pool.with_max_entries(2);
+
+        let _slots = [pool.alloc(1), pool.alloc(2)];
+        std::thread::scope(|scope| {
+            let waiters: Vec<_> = (0..2)
+                .map(|i| {
+                    let pool = &pool;
+                    scope.spawn(move || {
+                        pool.alloc_timeout(i, std::time::Duration::from_secs(10))
+                            .map(|slot| unsafe { pool.forget(slot) })
+                    })
+                })
+                .collect();
+            while pool.2.load(std::sync::atomic::Ordering::Relaxed) < 2 {
+                std::thread::yield_now();
+            }
+
+            // freeing both entries at once must wake both waiters
+            unsafe { pool.retain(|_| false) };
+            for waiter in waiters {
+                assert_eq!(waiter.join().unwrap(), Ok(()));
+            }
+        });
+    }
 }
@@ -4,39 +4,87 @@ use std::rc::Rc;
 
 /// A single thread, interior mutable memory Pool backed by a reference count.  This allows
 /// objects to hold references back to the pool to keep it alive without carrying a lifetime.
-pub struct RcPool<T: Sized>(Rc<RefCell<PoolInner<T>>>);
+/// The `FreelistStrategy` `L` defines how free entries are reused, the `PoolObserver` `O`
+/// gets notified about allocations and block growth.
+pub struct RcPool<T: Sized, L: FreelistStrategy = Ordered, O: PoolObserver = NoObserver>(
+    Rc<RefCell<PoolInner<T, L, O>>>,
+);
 
 impl<T> RcPool<T> {
     /// Creates a new `RcPool` for objects of type T.
     #[inline]
     #[must_use]
     pub fn new() -> Self {
+        Self::with_strategy()
+    }
+
+    /// Creates a new `RcPool` for objects of type T which allocates its blocks from
+    /// `allocator`.
+    #[inline]
+    #[must_use]
+    pub fn with_allocator(allocator: &'static dyn BlockAllocator) -> Self {
+        Self(Rc::new(RefCell::new(PoolInner::with_allocator(allocator))))
+    }
+}
+
+impl<T, L: FreelistStrategy> RcPool<T, L> {
+    /// Creates a new `RcPool` for objects of type T which reuses free entries according to
+    /// the `FreelistStrategy` `L`.
+    #[inline]
+    #[must_use]
+    pub fn with_strategy() -> Self {
         Self(Rc::new(RefCell::new(PoolInner::new())))
     }
 }
 
-impl<T> Clone for RcPool<T> {
+impl<T, L: FreelistStrategy, O: PoolObserver> RcPool<T, L, O> {
+    /// Creates a new `RcPool` for objects of type T which reports its events to
+    /// `observer`.
+    #[inline]
+    #[must_use]
+    pub fn with_observer(observer: O) -> Self {
+        Self::with_allocator_and_observer(&GlobalBlockAllocator, observer)
+    }
+
+    /// Creates a new `RcPool` for objects of type T which allocates its blocks from
+    /// `allocator` and reports its events to `observer`.
+    #[inline]
+    #[must_use]
+    pub fn with_allocator_and_observer(
+        allocator: &'static dyn BlockAllocator,
+        observer: O,
+    ) -> Self {
+        Self(Rc::new(RefCell::new(PoolInner::with_observer(
+            allocator, observer,
+        ))))
+    }
+}
+
+impl<T, L: FreelistStrategy, O: PoolObserver> Clone for RcPool<T, L, O> {
     fn clone(&self) -> Self {
         Self(self.0.clone())
     }
 }
 
-impl<T> PoolApi<T> for RcPool<T> {}
+impl<T, L: FreelistStrategy, O: PoolObserver> PoolApi<T> for RcPool<T, L, O> {}
+
+impl<T, L: FreelistStrategy, O: PoolObserver> PoolLock<T> for &RcPool<T, L, O> {
+    type Freelist = L;
+    type Observer = O;
 
-impl<T> PoolLock<T> for &RcPool<T> {
     #[inline]
-    fn with_lock<R, F: FnOnce(&mut PoolInner<T>) -> R>(self, f: F) -> R {
+    fn with_lock<R, F: FnOnce(&mut PoolInner<T, L, O>) -> R>(self, f: F) -> R {
         f(&mut self.0.borrow_mut())
     }
 }
 
-impl<T> Default for RcPool<T> {
+impl<T, L: FreelistStrategy, O: PoolObserver + Default> Default for RcPool<T, L, O> {
     fn default() -> Self {
-        Self::new()
+        Self::with_observer(O::default())
     }
 }
 
-impl<T> AsRef<RcPool<T>> for RcPool<T> {
+impl<T, L: FreelistStrategy, O: PoolObserver> AsRef<RcPool<T, L, O>> for RcPool<T, L, O> {
     fn as_ref(&self) -> &Self {
         self
     }
@@ -38,6 +38,32 @@ impl<T> Rc<T> {
         }
     }
 
+    /// Fallible variant of `Rc::new()`. Returns an `AllocError` when the pool can not
+    /// allocate an entry.
+    #[inline]
+    pub fn try_new(t: T, pool: impl AsRef<RcPool<RcInner<T>>>) -> Result<Self, AllocError> {
+        Ok(Self {
+            slot: pool.as_ref().try_alloc(RcInner::new(t))?.for_mutation(),
+            pool: pool.as_ref().clone(),
+        })
+    }
+
+    /// Allocate a `Rc` from a `RcPool` and initialize it with the value returned by `f`. The
+    /// value is written directly into the pool, this allows the compiler to construct it in
+    /// place, see `PoolApi::alloc_with()`.
+    #[inline]
+    pub fn new_with<F: FnOnce() -> T>(f: F, pool: impl AsRef<RcPool<RcInner<T>>>) -> Self {
+        let pool = pool.as_ref();
+        let mut slot = pool.alloc_uninit();
+        unsafe {
+            RcInner::init_with(slot.get_uninit().as_mut_ptr(), f);
+            Self {
+                slot: pool.assume_init(slot).for_mutation(),
+                pool: pool.clone(),
+            }
+        }
+    }
+
     /// Creates a Weak reference from a Rc.
     #[must_use]
     pub fn downgrade(this: &Self) -> Weak<T> {
@@ -72,7 +98,6 @@ impl<T: Default> Rc<T> {
 }
 
 impl<T> Clone for Rc<T> {
-    #[must_use]
     fn clone(&self) -> Self {
         self.slot.get().inc_strong();
         unsafe {
@@ -354,6 +379,20 @@ impl<T> RcInner<T> {
         }
     }
 
+    /// Initializes the uninitialized `RcInner` at `this` with a strong count of one and the
+    /// value returned by `f`, which is written to its final place without an intermediate
+    /// `RcInner`.
+    ///
+    /// # Safety
+    ///
+    /// `this` must be valid for writes and properly aligned.
+    #[inline]
+    pub(crate) unsafe fn init_with<F: FnOnce() -> T>(this: *mut Self, f: F) {
+        std::ptr::addr_of_mut!((*this).strong_count).write(Cell::new(1));
+        std::ptr::addr_of_mut!((*this).weak_count).write(Cell::new(0));
+        std::ptr::addr_of_mut!((*this).data).cast::<T>().write(f());
+    }
+
     #[inline]
     pub(crate) fn get_strong(&self) -> usize {
         self.strong_count.get()
@@ -401,4 +440,18 @@ mod tests {
         let pool = RcPool::new();
         let _myrc = Rc::new("Rc", &pool);
     }
+
+    #[test]
+    fn try_new() {
+        let pool: RcPool<RcInner<u64>> = RcPool::new();
+        pool.with_max_bytes(10 * std::mem::size_of::<Entry<RcInner<u64>>>());
+        let rcs: Vec<_> = (0..10).map(|i| Rc::try_new(i, &pool).unwrap()).collect();
+        assert_eq!(
+            Rc::try_new(10, &pool).err(),
+            Some(AllocError::CapacityExceeded)
+        );
+
+        drop(rcs);
+        assert_eq!(*Rc::try_new(10, &pool).unwrap(), 10);
+    }
 }
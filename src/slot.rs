@@ -1,4 +1,5 @@
 use std::marker::PhantomData;
+use std::mem::MaybeUninit;
 use std::pin::Pin;
 use std::ptr::NonNull;
 
@@ -26,8 +27,51 @@ impl<T, S: Policy> Slot<T, S> {
     }
 }
 
+impl<T, S: Policy> Slot<T, S> {
+    /// Converts the slot into a compact 32 bit index. The index is made of the number of the
+    /// block the slot belongs to and its offset within that block. Use
+    /// `pool.slot_from_index()` to get the slot back. Since slots do not know their pool, the
+    /// pool must be passed here.
+    ///
+    /// # Panics
+    ///
+    ///  * The slot is not from `pool`.
+    ///  * The index does not fit into 32 bits.
+    #[must_use]
+    pub fn into_index<P>(self, pool: &P) -> u32
+    where
+        P: PoolApi<T>,
+        for<'a> &'a P: PoolLock<T>,
+    {
+        pool.index_of(&self)
+    }
+
+    /// Checks that the slot does not refer to a freed entry. In debug builds or with the
+    /// `poison` feature blocks keep a bitmap of their freed entries and fill freed entries
+    /// with a poison pattern. The bitmap is checked here and by the accessors of the slot.
+    /// Otherwise this is a no-op. Use after free is not detected when the entry was already
+    /// allocated again.
+    ///
+    /// # Panics
+    ///
+    /// The entry of the slot is freed.
+    #[inline]
+    pub fn check_not_poisoned(&self) {
+        if POISONING && !Entry::<T>::IS_ZST {
+            assert!(
+                !is_freed_entry(self.0.as_ptr().cast()),
+                "Slot refers to a freed entry"
+            );
+        }
+    }
+}
+
 /// Base of the typestate policies.
-pub trait Policy {}
+pub trait Policy {
+    /// Whether slots of this policy hold an initialized object.
+    #[doc(hidden)]
+    const INITIALIZED: bool = true;
+}
 
 /// Implements how/if the content of a slot shall be dropped.
 pub trait DropPolicy: Policy {
@@ -43,10 +87,14 @@ pub trait DropPolicy: Policy {
 pub trait CanGetReference: Policy {}
 /// Permits destroying the Slot by taking the Value out of it.
 pub trait CanTakeValue: Policy {}
+/// Permits turning the Slot into a `Movable` whose object may be relocated.
+pub trait CanMove: Policy {}
 
 /// Holds uninitialized memory.
 pub enum Uninitialized {}
-impl Policy for Uninitialized {}
+impl Policy for Uninitialized {
+    const INITIALIZED: bool = false;
+}
 impl DropPolicy for Uninitialized {
     // The only case where this is a NOP
     #[inline]
@@ -59,6 +107,7 @@ impl Policy for Initialized {}
 impl DropPolicy for Initialized {}
 impl CanGetReference for Initialized {}
 impl CanTakeValue for Initialized {}
+impl CanMove for Initialized {}
 
 /// Initialized, mutable references are permitted.
 pub enum Mutable {}
@@ -66,8 +115,10 @@ impl Policy for Mutable {}
 impl DropPolicy for Mutable {}
 impl CanGetReference for Mutable {}
 impl CanTakeValue for Mutable {}
+impl CanMove for Mutable {}
 
-/// Initialized, pinned references are permitted.
+/// Initialized, pinned references are permitted. Pinned objects never move, thus these slots
+/// can not become `Movable`.
 pub enum Pinnable {}
 impl Policy for Pinnable {}
 impl DropPolicy for Pinnable {}
@@ -82,14 +133,16 @@ impl CanGetReference for NaNTagging {}
 impl CanTakeValue for NaNTagging {}
 
 /// Uninitialized slots are allocated with `pool.alloc_uninit()` as the name says they are
-/// only allocated yet but do not contain an initialized value. Similar to `MaybeUninit` one
-/// can obtain a reference to an `Entry` to this slot and then `write()` a value to it. When
-/// done so the slot is transformed into a initialized slot with `assume_init()`.
+/// only allocated yet but do not contain an initialized value. One can obtain a `MaybeUninit`
+/// reference to this slot and then `write()` a value to it. When done so the slot is
+/// transformed into a initialized slot with `assume_init()`.
 impl<T> Slot<T, Uninitialized> {
-    /// Get a reference to the uninitialized memory at slot.
+    /// Get a reference to the uninitialized memory at slot. This covers only the object, not
+    /// the whole `Entry`: all slots of a zero sized type share the same entry.
     #[inline]
-    pub fn get_uninit(&mut self) -> &mut Entry<T> {
-        unsafe { self.0.as_mut() }
+    pub fn get_uninit(&mut self) -> &mut MaybeUninit<T> {
+        // SAFETY: ManuallyDrop<T> and MaybeUninit<T> have the same layout as T
+        unsafe { &mut *Entry::data_ptr(self.0).cast::<MaybeUninit<T>>() }
     }
 
     /// Tags the object at slot as initialized. Return an initialized Slot.
@@ -134,7 +187,8 @@ impl<T> Slot<T, Mutable> {
     /// Get a mutable reference to the object in slot, where slot must be an allocated slot.
     #[inline]
     pub fn get_mut(&mut self) -> &mut T {
-        unsafe { &mut self.0.as_mut().data }
+        self.check_not_poisoned();
+        unsafe { &mut *Entry::data_ptr(self.0) }
     }
 
     /// Copies a slot handle.
@@ -157,7 +211,8 @@ impl<T> Slot<T, Pinnable> {
     /// give Pin guarantees for them. One only need to make sure not to violate the Pin
     /// guarantees by calling unsafe functions
     pub fn get_pin(&mut self) -> Pin<&mut T> {
-        unsafe { Pin::new_unchecked(&mut self.0.as_mut().data) }
+        self.check_not_poisoned();
+        unsafe { Pin::new_unchecked(&mut *Entry::data_ptr(self.0)) }
     }
 }
 
@@ -169,6 +224,7 @@ impl<T, S: CanGetReference> Slot<T, S> {
     #[inline]
     #[must_use]
     pub fn get(&self) -> &T {
+        self.check_not_poisoned();
         unsafe { &self.0.as_ref().data }
     }
 }
@@ -195,6 +251,10 @@ impl<T> Slot<T, NaNTagging> {
     /// # Safety
     ///
     /// The identifier must point to the same allocation as the slot where it was got from.
+    ///
+    /// # Panics
+    ///
+    /// When the identifier is zero.
     #[inline]
     #[must_use]
     pub unsafe fn from_u64(id: u64) -> Self {
@@ -213,6 +273,10 @@ impl<T> Slot<T, NaNTagging> {
     ///
     /// The identifier must point to the same allocation as the slot where it was got from. It
     /// may have the auxiliary bits set.
+    ///
+    /// # Panics
+    ///
+    /// When the identifier is zero after masking.
     #[inline]
     #[must_use]
     pub unsafe fn from_u64_masked(id: u64) -> Self {
@@ -1,9 +1,51 @@
 #![cfg(feature = "stpool")]
 use std::cell::RefCell;
-use threadcell::{Guard as STPoolGuard, ThreadCell};
+use threadcell::ThreadCell;
 
 use crate::*;
 
+/// The thread that acquired a `STPool` last, tracing events about failed ownership transfers
+/// name it. Only recorded with the `tracing` feature.
+#[cfg(feature = "tracing")]
+struct Owner(std::sync::Mutex<Option<std::thread::Thread>>);
+
+#[cfg(feature = "tracing")]
+impl Owner {
+    const fn new() -> Self {
+        Self(std::sync::Mutex::new(None))
+    }
+
+    /// Records the current thread as owner.
+    fn set_current(&self) {
+        *self.0.lock().unwrap_or_else(|err| err.into_inner()) = Some(std::thread::current());
+    }
+
+    /// Forgets the owner, must be called before the pool is released.
+    fn clear(&self) {
+        *self.0.lock().unwrap_or_else(|err| err.into_inner()) = None;
+    }
+
+    fn get(&self) -> Option<std::thread::Thread> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+struct Owner;
+
+#[cfg(not(feature = "tracing"))]
+impl Owner {
+    const fn new() -> Self {
+        Self
+    }
+
+    #[inline(always)]
+    fn set_current(&self) {}
+
+    #[inline(always)]
+    fn clear(&self) {}
+}
+
 /// A single thread, interior mutable memory Pool holding objects of type T that can
 /// cooperatively moved between threads.
 ///
@@ -37,14 +79,65 @@ use crate::*;
 /// //            dropping boxes, the thread will panic!
 /// TBox::<u8, MyTag>::pool().release().expect("did not own the pool");
 /// ```
-pub struct STPool<T: Sized>(ThreadCell<RefCell<PoolInner<T>>>);
+pub struct STPool<T: Sized, L: FreelistStrategy = Ordered, O: PoolObserver = NoObserver>(
+    ThreadCell<RefCell<PoolInner<T, L, O>>>,
+    Owner,
+);
 
 impl<T> STPool<T> {
     /// Creates a new `STPool` for objects of type T.
     #[inline]
     #[must_use]
     pub const fn new() -> Self {
-        Self(ThreadCell::new_disowned(RefCell::new(PoolInner::new())))
+        Self::with_strategy()
+    }
+
+    /// Creates a new `STPool` for objects of type T which allocates its blocks from
+    /// `allocator`.
+    #[inline]
+    #[must_use]
+    pub const fn with_allocator(allocator: &'static dyn BlockAllocator) -> Self {
+        Self(
+            ThreadCell::new_disowned(RefCell::new(PoolInner::with_allocator(allocator))),
+            Owner::new(),
+        )
+    }
+}
+
+impl<T, L: FreelistStrategy> STPool<T, L> {
+    /// Creates a new `STPool` for objects of type T which reuses free entries according to
+    /// the `FreelistStrategy` `L`.
+    #[inline]
+    #[must_use]
+    pub const fn with_strategy() -> Self {
+        Self(
+            ThreadCell::new_disowned(RefCell::new(PoolInner::new())),
+            Owner::new(),
+        )
+    }
+}
+
+impl<T, L: FreelistStrategy, O: PoolObserver> STPool<T, L, O> {
+    /// Creates a new `STPool` for objects of type T which reports its events to
+    /// `observer`.
+    #[inline]
+    #[must_use]
+    pub const fn with_observer(observer: O) -> Self {
+        Self::with_allocator_and_observer(&GlobalBlockAllocator, observer)
+    }
+
+    /// Creates a new `STPool` for objects of type T which allocates its blocks from
+    /// `allocator` and reports its events to `observer`.
+    #[inline]
+    #[must_use]
+    pub const fn with_allocator_and_observer(
+        allocator: &'static dyn BlockAllocator,
+        observer: O,
+    ) -> Self {
+        Self(
+            ThreadCell::new_disowned(RefCell::new(PoolInner::with_observer(allocator, observer))),
+            Owner::new(),
+        )
     }
 
     /// Acquire the ownership of the `STPool` by the current thread.  Must be called before
@@ -52,7 +145,11 @@ impl<T> STPool<T> {
     /// released. Returns `Ok(())` when the pool was successful acquired and
     /// `Err(PoolOwnershipError)` when the current thread could not acquire the pool.
     pub fn acquire(&self) -> Result<(), PoolOwnershipError> {
-        if self.0.try_acquire() {
+        let acquired = self.0.try_acquire();
+        #[cfg(feature = "tracing")]
+        self.trace_ownership("acquire", acquired);
+        if acquired {
+            self.1.set_current();
             Ok(())
         } else {
             Err(PoolOwnershipError)
@@ -64,7 +161,16 @@ impl<T> STPool<T> {
     /// up.  Returns `Ok(())` when the pool was successful released and
     /// `Err(PoolOwnershipError)` when the current thread did not own the pool.
     pub fn release(&self) -> Result<(), PoolOwnershipError> {
-        if self.0.try_release() {
+        // only the owning thread can release the pool, the owner is cleared before another
+        // thread can acquire it
+        let released = self.0.is_owned();
+        if released {
+            self.1.clear();
+            self.0.release();
+        }
+        #[cfg(feature = "tracing")]
+        self.trace_ownership("release", released);
+        if released {
             Ok(())
         } else {
             Err(PoolOwnershipError)
@@ -77,10 +183,13 @@ impl<T> STPool<T> {
     /// own the pool. The returned guard itself is opaque, it just needs to stay around as
     /// long as needed.
     #[must_use = "the returned guard holds the threads ownership of the pool"]
-    pub fn acquire_guard(&self) -> Result<STPoolGuard<RefCell<PoolInner<T>>>, PoolOwnershipError> {
-        let guard = STPoolGuard::new(&self.0);
-        if guard.inner().try_acquire() {
-            Ok(guard)
+    pub fn acquire_guard(&self) -> Result<STPoolGuard<'_, T, L, O>, PoolOwnershipError> {
+        let acquired = self.0.try_acquire();
+        #[cfg(feature = "tracing")]
+        self.trace_ownership("acquire_guard", acquired);
+        if acquired {
+            self.1.set_current();
+            Ok(STPoolGuard(self))
         } else {
             Err(PoolOwnershipError)
         }
@@ -93,21 +202,125 @@ impl<T> STPool<T> {
     ///
     /// It is UB to `force_release()` a pool from a thread that is still using it.
     pub unsafe fn force_release(&self) {
+        #[cfg(feature = "tracing")]
+        {
+            let thread = std::thread::current();
+            let owner = self.1.get();
+            let owner_name = owner.as_ref().and_then(|owner| owner.name());
+            tracing::warn!(
+                pool = ?(self as *const Self),
+                entry_type = std::any::type_name::<T>(),
+                thread = ?thread.id(),
+                thread_name = thread.name().unwrap_or("<unnamed>"),
+                owner = ?owner.as_ref().map(std::thread::Thread::id),
+                owner_name = owner_name.unwrap_or("<unnamed>"),
+                "STPool force_release"
+            );
+        }
+        self.1.clear();
         self.0.steal().release();
     }
+
+    /// Emits a tracing event for an ownership transfer of this pool by the current thread.
+    /// Failed transfers are reported as warnings naming the thread that owns the pool.
+    #[cfg(feature = "tracing")]
+    fn trace_ownership(&self, action: &str, success: bool) {
+        let thread = std::thread::current();
+        if success {
+            tracing::debug!(
+                pool = ?(self as *const Self),
+                entry_type = std::any::type_name::<T>(),
+                thread = ?thread.id(),
+                thread_name = thread.name().unwrap_or("<unnamed>"),
+                "STPool {action}"
+            );
+        } else {
+            let owner = self.1.get();
+            let owner_name = owner.as_ref().and_then(|owner| owner.name());
+            tracing::warn!(
+                pool = ?(self as *const Self),
+                entry_type = std::any::type_name::<T>(),
+                thread = ?thread.id(),
+                thread_name = thread.name().unwrap_or("<unnamed>"),
+                owner = ?owner.as_ref().map(std::thread::Thread::id),
+                owner_name = owner_name.unwrap_or("<unnamed>"),
+                "STPool {action} failed"
+            );
+        }
+    }
 }
 
-impl<T> PoolApi<T> for STPool<T> {}
+/// Holds the ownership of a `STPool` by the current thread, obtained by
+/// `STPool::acquire_guard()`. The pool is released when the guard is dropped, unless it was
+/// released explicitly before.
+pub struct STPoolGuard<'a, T, L: FreelistStrategy = Ordered, O: PoolObserver = NoObserver>(
+    &'a STPool<T, L, O>,
+);
+
+impl<T, L: FreelistStrategy, O: PoolObserver> Drop for STPoolGuard<'_, T, L, O> {
+    fn drop(&mut self) {
+        if self.0 .0.is_owned() {
+            let _ = self.0.release();
+        }
+    }
+}
+
+impl<T, L: FreelistStrategy, O: PoolObserver> PoolApi<T> for STPool<T, L, O> {}
+
+impl<T, L: FreelistStrategy, O: PoolObserver> PoolLock<T> for &STPool<T, L, O> {
+    type Freelist = L;
+    type Observer = O;
 
-impl<T> PoolLock<T> for &STPool<T> {
     #[inline]
-    fn with_lock<R, F: FnOnce(&mut PoolInner<T>) -> R>(self, f: F) -> R {
+    #[cfg(not(feature = "tracing"))]
+    fn with_lock<R, F: FnOnce(&mut PoolInner<T, L, O>) -> R>(self, f: F) -> R {
         f(&mut self.0.get().borrow_mut())
     }
+
+    #[inline]
+    #[cfg(feature = "tracing")]
+    fn with_lock<R, F: FnOnce(&mut PoolInner<T, L, O>) -> R>(self, f: F) -> R {
+        let cell = self.0.try_get().unwrap_or_else(|| {
+            // report the owner before `get()` panics
+            self.trace_ownership("access", false);
+            self.0.get()
+        });
+        f(&mut cell.borrow_mut())
+    }
 }
 
-impl<T> Default for STPool<T> {
+impl<T, L: FreelistStrategy, O: PoolObserver + Default> Default for STPool<T, L, O> {
     fn default() -> Self {
-        Self::new()
+        Self::with_observer(O::default())
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn owner() {
+        let pool: STPool<u64> = STPool::new();
+        assert!(pool.1.get().is_none());
+        pool.acquire().unwrap();
+        assert_eq!(
+            pool.1.get().map(|owner| owner.id()),
+            Some(std::thread::current().id())
+        );
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| assert!(pool.acquire().is_err()));
+        });
+        pool.release().unwrap();
+        assert!(pool.1.get().is_none());
+
+        let guard = pool.acquire_guard().unwrap();
+        assert_eq!(
+            pool.1.get().map(|owner| owner.id()),
+            Some(std::thread::current().id())
+        );
+        drop(guard);
+        assert!(pool.1.get().is_none());
     }
 }
@@ -30,6 +30,16 @@ impl<T> Sc<T> {
         }
     }
 
+    /// Fallible variant of `Sc::new()`. Returns an `AllocError` when the pool can not
+    /// allocate an entry.
+    #[inline]
+    pub fn try_new(t: T, pool: impl AsRef<RcPool<ScInner<T>>>) -> Result<Self, AllocError> {
+        Ok(Self {
+            slot: pool.as_ref().try_alloc(ScInner::new(t))?.for_mutation(),
+            pool: pool.as_ref().clone(),
+        })
+    }
+
     /// Associated function that returns the number of strong counters of this Sc.
     #[must_use]
     pub fn strong_count(this: &Self) -> usize {
@@ -46,7 +56,6 @@ impl<T: Default> Sc<T> {
 }
 
 impl<T> Clone for Sc<T> {
-    #[must_use]
     fn clone(&self) -> Self {
         unsafe {
             self.slot.get().inc_strong();
@@ -278,4 +287,18 @@ mod tests {
         let pool = RcPool::new();
         let _mysc = Sc::new("Sc", &pool);
     }
+
+    #[test]
+    fn try_new() {
+        let pool: RcPool<ScInner<u64>> = RcPool::new();
+        pool.with_max_bytes(10 * std::mem::size_of::<Entry<ScInner<u64>>>());
+        let scs: Vec<_> = (0..10).map(|i| Sc::try_new(i, &pool).unwrap()).collect();
+        assert_eq!(
+            Sc::try_new(10, &pool).err(),
+            Some(AllocError::CapacityExceeded)
+        );
+
+        drop(scs);
+        assert_eq!(*Sc::try_new(10, &pool).unwrap(), 10);
+    }
 }
@@ -70,6 +70,27 @@ where
         }
     }
 
+    /// Fallible variant of `TBox::new()`. Returns an `AllocError` when the pool can not
+    /// allocate an entry.
+    #[inline]
+    pub fn try_new(t: T, _tag: TAG) -> Result<Self, AllocError> {
+        Ok(Self {
+            slot: T::get_static().try_alloc(t)?.for_mutation(),
+            tag: PhantomData,
+        })
+    }
+
+    /// Allocate a `TBox` from a static pool and initialize it with the value returned by
+    /// `f`. The value is written directly into the pool, this allows the compiler to construct
+    /// it in place, see `PoolApi::alloc_with()`.
+    #[inline]
+    pub fn new_with<F: FnOnce() -> T>(f: F, _tag: TAG) -> Self {
+        Self {
+            slot: T::get_static().alloc_with(f).for_mutation(),
+            tag: PhantomData,
+        }
+    }
+
     /// Allocate a `TBox` from a static Pool with inferred or turbofish tag.
     #[inline]
     pub fn new_notag(t: T) -> Self {
@@ -335,6 +356,9 @@ mod tests {
     define_tbox_pool!((): &'static str);
     define_tbox_pool!((): u64);
 
+    struct Limited;
+    define_tbox_pool!(Limited: u64);
+
     #[test]
     #[serial]
     fn smoke() {
@@ -347,6 +371,29 @@ mod tests {
         TBox::<&'static str, ()>::pool().release().unwrap();
     }
 
+    #[test]
+    #[serial]
+    fn try_new() {
+        let pool = TBox::<u64, Limited>::pool();
+        pool.acquire().unwrap();
+        pool.with_max_bytes(10 * std::mem::size_of::<Entry<u64>>());
+
+        {
+            let boxes: Vec<_> = (0..10)
+                .map(|i| TBox::try_new(i, Limited).unwrap())
+                .collect();
+            assert_eq!(
+                TBox::try_new(10, Limited).err(),
+                Some(AllocError::CapacityExceeded)
+            );
+
+            drop(boxes);
+            assert_eq!(*TBox::try_new(10, Limited).unwrap(), 10);
+        }
+
+        pool.release().unwrap();
+    }
+
     #[test]
     #[serial]
     #[ignore]
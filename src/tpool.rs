@@ -1,22 +1,176 @@
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
 use crate::*;
 
 #[cfg(not(feature = "parking_lot"))]
-use std::sync::Mutex;
+use std::sync::{Condvar, Mutex};
 
 #[cfg(feature = "parking_lot")]
-use parking_lot::Mutex;
+use parking_lot::{Condvar, Mutex};
 
 /// A threadsafe, interior mutable memory Pool holding objects of type T.  The whole pool is
 /// protected by a single lock, thread safety is not meant to scale here. When scalability
 /// over many threads is needed then onsen is not the right tool.
-pub struct TPool<T: Sized>(Mutex<PoolInner<T>>);
+///
+/// When the pool is limited with `with_max_entries()` or `with_max_bytes()` threads can wait
+/// with `alloc_blocking()` or `alloc_timeout()` until other threads free entries.
+///
+/// The `FreelistStrategy` `L` defines how free entries are reused, the `PoolObserver` `O`
+/// gets notified about allocations and block growth.
+pub struct TPool<T: Sized, L: FreelistStrategy = Ordered, O: PoolObserver = NoObserver>(
+    Mutex<PoolInner<T, L, O>>,
+    Condvar,
+    /// Number of threads waiting for a free entry. Only changed while the mutex is held,
+    /// thus freeing threads see every waiter that could have missed their free.
+    pub(crate) AtomicUsize,
+);
 
 impl<T> TPool<T> {
     /// Creates a new `TPool` for objects of type T.
     #[inline]
     #[must_use]
     pub const fn new() -> Self {
-        Self(Mutex::new(PoolInner::new()))
+        Self::with_strategy()
+    }
+
+    /// Creates a new `TPool` for objects of type T which allocates its blocks from
+    /// `allocator`.
+    #[inline]
+    #[must_use]
+    pub const fn with_allocator(allocator: &'static dyn BlockAllocator) -> Self {
+        Self(
+            Mutex::new(PoolInner::with_allocator(allocator)),
+            Condvar::new(),
+            AtomicUsize::new(0),
+        )
+    }
+}
+
+impl<T, L: FreelistStrategy> TPool<T, L> {
+    /// Creates a new `TPool` for objects of type T which reuses free entries according to
+    /// the `FreelistStrategy` `L`.
+    #[inline]
+    #[must_use]
+    pub const fn with_strategy() -> Self {
+        Self(
+            Mutex::new(PoolInner::new()),
+            Condvar::new(),
+            AtomicUsize::new(0),
+        )
+    }
+}
+
+impl<T, L: FreelistStrategy, O: PoolObserver> TPool<T, L, O> {
+    /// Creates a new `TPool` for objects of type T which reports its events to `observer`.
+    #[inline]
+    #[must_use]
+    pub const fn with_observer(observer: O) -> Self {
+        Self::with_allocator_and_observer(&GlobalBlockAllocator, observer)
+    }
+
+    /// Creates a new `TPool` for objects of type T which allocates its blocks from
+    /// `allocator` and reports its events to `observer`.
+    #[inline]
+    #[must_use]
+    pub const fn with_allocator_and_observer(
+        allocator: &'static dyn BlockAllocator,
+        observer: O,
+    ) -> Self {
+        Self(
+            Mutex::new(PoolInner::with_observer(allocator, observer)),
+            Condvar::new(),
+            AtomicUsize::new(0),
+        )
+    }
+
+    /// Allocates a new slot from the pool and initializes it with the supplied object. When
+    /// the pool reached its capacity limit this waits until some other thread frees an
+    /// entry.
+    ///
+    /// # Panics
+    ///
+    /// When the allocation fails for other reasons than the capacity limit.
+    #[must_use = "Slot is required for freeing memory, dropping it will leak"]
+    pub fn alloc_blocking(&self, t: T) -> Slot<T, Initialized> {
+        Slot::new(self.wait_for_entry(t, None).expect("Allocation failure"))
+    }
+
+    /// Allocates a new slot from the pool and initializes it with the supplied object. When
+    /// the pool reached its capacity limit this waits up to `timeout` until some other
+    /// thread frees an entry. Returns `AllocError::CapacityExceeded` when the timeout
+    /// expired.
+    pub fn alloc_timeout(
+        &self,
+        t: T,
+        timeout: Duration,
+    ) -> Result<Slot<T, Initialized>, AllocError> {
+        Ok(Slot::new(
+            self.wait_for_entry(t, Some(Instant::now() + timeout))?,
+        ))
+    }
+
+    /// Allocates an entry initialized with `t`, waits for frees while the pool is at its
+    /// capacity limit.
+    #[cfg(not(feature = "parking_lot"))]
+    fn wait_for_entry(
+        &self,
+        t: T,
+        deadline: Option<Instant>,
+    ) -> Result<NonNull<Entry<T>>, AllocError> {
+        let mut pool = self.0.lock().expect("Failed to lock Mutex");
+        loop {
+            match pool.alloc_entry_in_block() {
+                Ok((block, entry)) => return Ok(unsafe { pool.init_entry(block, entry, t) }),
+                Err(AllocError::CapacityExceeded) => {}
+                Err(error) => return Err(error),
+            }
+
+            let now = Instant::now();
+            if deadline.is_some_and(|deadline| now >= deadline) {
+                return Err(AllocError::CapacityExceeded);
+            }
+            self.2.fetch_add(1, Ordering::Relaxed);
+            pool = if let Some(deadline) = deadline {
+                self.1
+                    .wait_timeout(pool, deadline - now)
+                    .expect("Failed to lock Mutex")
+                    .0
+            } else {
+                self.1.wait(pool).expect("Failed to lock Mutex")
+            };
+            self.2.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Allocates an entry initialized with `t`, waits for frees while the pool is at its
+    /// capacity limit.
+    #[cfg(feature = "parking_lot")]
+    fn wait_for_entry(
+        &self,
+        t: T,
+        deadline: Option<Instant>,
+    ) -> Result<NonNull<Entry<T>>, AllocError> {
+        let mut pool = self.0.lock();
+        loop {
+            match pool.alloc_entry_in_block() {
+                Ok((block, entry)) => return Ok(unsafe { pool.init_entry(block, entry, t) }),
+                Err(AllocError::CapacityExceeded) => {}
+                Err(error) => return Err(error),
+            }
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(AllocError::CapacityExceeded);
+            }
+            self.2.fetch_add(1, Ordering::Relaxed);
+            if let Some(deadline) = deadline {
+                self.1.wait_until(&mut pool, deadline);
+            } else {
+                self.1.wait(&mut pool);
+            }
+            self.2.fetch_sub(1, Ordering::Relaxed);
+        }
     }
 
     /// Mock the `STPool` API's to make `TPool` a drop in replacement.
@@ -43,24 +197,41 @@ impl<T> TPool<T> {
     pub unsafe fn force_release(&self) {}
 }
 
-impl<T> PoolApi<T> for TPool<T> {}
+impl<T, L: FreelistStrategy, O: PoolObserver> PoolApi<T> for TPool<T, L, O> {}
+
+impl<T, L: FreelistStrategy, O: PoolObserver> PoolLock<T> for &TPool<T, L, O> {
+    type Freelist = L;
+    type Observer = O;
 
-impl<T> PoolLock<T> for &TPool<T> {
     #[inline]
     #[cfg(not(feature = "parking_lot"))]
-    fn with_lock<R, F: FnOnce(&mut PoolInner<T>) -> R>(self, f: F) -> R {
+    fn with_lock<R, F: FnOnce(&mut PoolInner<T, L, O>) -> R>(self, f: F) -> R {
         f(&mut self.0.lock().expect("Failed to lock Mutex"))
     }
 
     #[inline]
     #[cfg(feature = "parking_lot")]
-    fn with_lock<R, F: FnOnce(&mut PoolInner<T>) -> R>(self, f: F) -> R {
+    fn with_lock<R, F: FnOnce(&mut PoolInner<T, L, O>) -> R>(self, f: F) -> R {
         f(&mut self.0.lock())
     }
+
+    #[inline]
+    fn notify_free(self) {
+        if self.2.load(Ordering::Relaxed) > 0 {
+            self.1.notify_one();
+        }
+    }
+
+    #[inline]
+    fn notify_free_all(self) {
+        if self.2.load(Ordering::Relaxed) > 0 {
+            self.1.notify_all();
+        }
+    }
 }
 
-impl<T> Default for TPool<T> {
+impl<T, L: FreelistStrategy, O: PoolObserver + Default> Default for TPool<T, L, O> {
     fn default() -> Self {
-        Self::new()
+        Self::with_observer(O::default())
     }
 }
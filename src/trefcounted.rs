@@ -124,7 +124,6 @@ where
     T: AssocStatic<TRcPool<T>, TAG> + 'static,
     TAG: 'static,
 {
-    #[must_use]
     fn clone(&self) -> Self {
         self.slot.get().inc_strong();
         unsafe {
@@ -106,7 +106,6 @@ where
     T: AssocStatic<TScPool<T>, TAG> + 'static,
     TAG: 'static,
 {
-    #[must_use]
     fn clone(&self) -> Self {
         self.slot.get().inc_strong();
         unsafe {
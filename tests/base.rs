@@ -50,3 +50,25 @@ fn alloc_uninit() {
         pool.free(memory);
     }
 }
+
+#[test]
+fn inferred_type() {
+    let pool = Pool::new();
+    let memory = pool.alloc(1u64);
+    assert_eq!(memory.get(), &1);
+    unsafe {
+        pool.free(memory);
+    }
+
+    let pool = TPool::new();
+    let memory = pool.alloc(2u64);
+    unsafe {
+        pool.free(memory);
+    }
+
+    let pool = RcPool::new();
+    let memory = pool.alloc(3u64);
+    unsafe {
+        pool.free(memory);
+    }
+}
@@ -13,6 +13,13 @@ fn deref() {
     assert_eq!(*mybox, "Boxed");
 }
 
+#[test]
+fn new_with() {
+    let pool: RcPool<&str> = RcPool::new();
+    let mybox = Box::new_with(|| "Boxed", &pool);
+    assert_eq!(*mybox, "Boxed");
+}
+
 #[test]
 fn deref_mut() {
     let pool: RcPool<&str> = RcPool::new();
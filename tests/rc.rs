@@ -13,6 +13,14 @@ fn macro_test() {
     assert_eq!(*myrc, "Rc");
 }
 
+#[test]
+fn new_with() {
+    let pool = RcPool::new();
+    let myrc = Rc::new_with(|| "Rc", &pool);
+    assert_eq!(*myrc, "Rc");
+    assert_eq!(Rc::strong_count(&myrc), 1);
+}
+
 #[test]
 fn clone() {
     let pool = RcPool::new();
@@ -26,6 +26,17 @@ fn deref() {
     TBox::<&'static str, Test>::pool().release().unwrap();
 }
 
+#[test]
+#[serial]
+fn new_with() {
+    TBox::<&'static str, Test>::pool().acquire().unwrap();
+    {
+        let mybox = TBox::new_with(|| "Boxed", Test);
+        assert_eq!(*mybox, "Boxed");
+    }
+    TBox::<&'static str, Test>::pool().release().unwrap();
+}
+
 #[test]
 #[serial]
 fn deref_mut() {